@@ -1,5 +1,7 @@
-use cpal::traits::{DeviceTrait, HostTrait};
-use rodio::{OutputStream, OutputStreamHandle, Sink, Source};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use rodio::dynamic_mixer::{self, DynamicMixerController};
+use rodio::source::UniformSourceIterator;
+use rodio::{Sink, Source};
 use symphonia::core::audio::SampleBuffer;
 use symphonia::core::codecs::{Decoder as SymphoniaDecoder, DecoderOptions};
 use symphonia::core::formats::{FormatOptions, FormatReader, SeekMode, SeekTo};
@@ -7,14 +9,18 @@ use symphonia::core::io::MediaSourceStream;
 use symphonia::core::meta::MetadataOptions;
 use symphonia::core::probe::Hint;
 use symphonia::core::units::Time;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::fs::{self, File};
 use std::io::BufReader;
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter, Manager, State};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use crate::devices::{self, DeviceInfo, DeviceSelection, DEFAULT_DEVICE};
+use crate::dsp::{EffectChain, EffectsSettings};
+use crate::loudness::{self, LoudnessInfo};
 use ogg::PacketReader;
 use opus::{Decoder as OpusDecoder, Channels};
 
@@ -25,38 +31,120 @@ fn is_opus_ogg(path: &str) -> bool {
         
         if let Ok(Some(packet)) = packet_reader.read_packet() {
             let data = packet.data;
-            if data.len() >= 8 {
-                if &data[0..8] == b"OpusHead" {
-                    return true;
-                }
+            if data.len() >= 8 && &data[0..8] == b"OpusHead" {
+                return true;
             }
         }
     }
     false
 }
 
+/// Position of a decoder in its file, advanced for every frame pulled from it.
+/// Shared with the instance so progress, seek and migration follow the audio itself.
+pub struct Playhead {
+    frames: AtomicU64,
+    sample_rate: u32,
+}
+
+impl Playhead {
+    fn new(sample_rate: u32) -> Arc<Self> {
+        Arc::new(Self {
+            frames: AtomicU64::new(0),
+            sample_rate: sample_rate.max(1),
+        })
+    }
+
+    fn set(&self, position: Duration) {
+        let frames = (position.as_secs_f64() * self.sample_rate as f64).round() as u64;
+        self.frames.store(frames, Ordering::Relaxed);
+    }
+
+    pub fn position(&self) -> Duration {
+        Duration::from_secs_f64(self.frames.load(Ordering::Relaxed) as f64 / self.sample_rate as f64)
+    }
+}
+
+/// Advances a [`Playhead`] once per complete frame of interleaved samples.
+struct FrameCounter {
+    playhead: Arc<Playhead>,
+    channels: u16,
+    sample_index: u16,
+}
+
+impl FrameCounter {
+    fn new(channels: u16, sample_rate: u32) -> Self {
+        Self {
+            playhead: Playhead::new(sample_rate),
+            channels: channels.max(1),
+            sample_index: 0,
+        }
+    }
+
+    fn tick(&mut self) {
+        self.sample_index += 1;
+        if self.sample_index == self.channels {
+            self.sample_index = 0;
+            self.playhead.frames.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn reset(&mut self, position: Duration) {
+        self.sample_index = 0;
+        self.playhead.set(position);
+    }
+
+    fn frames(&self) -> u64 {
+        self.playhead.frames.load(Ordering::Relaxed)
+    }
+}
+
+/// Opus always decodes at 48 kHz and granule positions count in that rate.
+const OPUS_RATE: u32 = 48000;
+/// Frames decoded before a seek target so the decoder has converged (RFC 7845).
+const OPUS_PREROLL: u64 = 3840;
+
+/// Granule position of the last page, read from the tail of the file.
+fn last_granule_position(path: &str) -> Option<u64> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let mut file = File::open(path).ok()?;
+    // A page is at most ~64 KiB, so the last complete one starts within this window
+    let len = file.metadata().ok()?.len();
+    let window = len.min(128 * 1024);
+    file.seek(SeekFrom::Start(len - window)).ok()?;
+    let mut tail = Vec::with_capacity(window as usize);
+    file.read_to_end(&mut tail).ok()?;
+
+    (0..tail.len().saturating_sub(14))
+        .rev()
+        .filter(|&i| &tail[i..i + 4] == b"OggS" && tail[i + 4] == 0)
+        .map(|i| u64::from_le_bytes(tail[i + 6..i + 14].try_into().unwrap()))
+        .find(|&granule| granule != u64::MAX)
+}
+
 struct OpusOggSource {
     packet_reader: PacketReader<BufReader<File>>,
     decoder: OpusDecoder,
     sample_buffer: Vec<f32>,
     buffer_offset: usize,
     channels: u16,
-    sample_rate: u32,
-    total_duration: Option<Duration>,
-    #[allow(dead_code)]
-    current_position_bytes: u64,
+    pre_skip: u64,
+    /// Playable length in frames, after pre-skip and end trimming.
+    total_frames: Option<u64>,
+    skip_samples: usize,
     eos: bool,
+    counter: FrameCounter,
 }
 
 impl OpusOggSource {
-    fn new(path: &str, _start_time: Option<Duration>) -> Result<Self, String> {
+    fn new(path: &str, start_time: Option<Duration>) -> Result<Self, String> {
         let file = File::open(path).map_err(|e| format!("Failed to open Opus file: {}", e))?;
-        let file_size = file.metadata().map(|m| m.len()).unwrap_or(0);
+        let last_granule = last_granule_position(path);
         let reader = BufReader::new(file);
         let mut packet_reader = PacketReader::new(reader);
         
         let mut channels = 2u16;
-        let mut sample_rate = 48000u32;
+        let mut pre_skip = 0u64;
         
         if let Ok(Some(packet)) = packet_reader.read_packet() {
             let data = &packet.data;
@@ -64,34 +152,109 @@ impl OpusOggSource {
                 let version = data[8];
                 if version == 1 {
                     channels = data[9] as u16;
-                    sample_rate = u32::from_le_bytes([data[12], data[13], data[14], data[15]]);
+                    pre_skip = u16::from_le_bytes([data[10], data[11]]) as u64;
                 }
             }
         }
         
         packet_reader.seek_absgp(None, 0).ok();
         
-        let decoder = OpusDecoder::new(sample_rate, if channels == 1 { Channels::Mono } else { Channels::Stereo })
+        let decoder = OpusDecoder::new(OPUS_RATE, if channels == 1 { Channels::Mono } else { Channels::Stereo })
             .map_err(|e| format!("Failed to create Opus decoder: {}", e))?;
-        
-        let estimated_duration = if sample_rate > 0 && file_size > 0 {
-            let avg_bitrate = 128_000.0;
-            Some(Duration::from_secs_f64(file_size as f64 * 8.0 / avg_bitrate))
-        } else {
-            None
-        };
-        
-        Ok(Self {
+
+        let mut source = Self {
             packet_reader,
             decoder,
             sample_buffer: Vec::new(),
             buffer_offset: 0,
             channels,
-            sample_rate,
-            total_duration: estimated_duration,
-            current_position_bytes: 0,
+            pre_skip,
+            total_frames: last_granule.map(|g| g.saturating_sub(pre_skip)),
+            skip_samples: pre_skip as usize * channels as usize,
             eos: false,
-        })
+            counter: FrameCounter::new(channels, OPUS_RATE),
+        };
+
+        if let Some(seek_time) = start_time {
+            source.seek_to(seek_time);
+        }
+
+        Ok(source)
+    }
+
+    /// Decodes one audio packet, skipping the header packets.
+    fn decode_packet(&mut self, data: &[u8]) -> Option<Vec<f32>> {
+        if data.is_empty() || data.starts_with(b"OpusHead") || data.starts_with(b"OpusTags") {
+            return None;
+        }
+        let max_frame_size = 5760;
+        let mut output = vec![0f32; max_frame_size * self.channels as usize];
+        let frames = self.decoder.decode_float(data, &mut output, false).ok()?;
+        output.truncate(frames * self.channels as usize);
+        Some(output)
+    }
+
+    /// Bisects to the page holding `granule` and decodes it. Returns the granule
+    /// position of the first decoded frame, or `None` if it cannot be known.
+    fn seek_page(&mut self, granule: u64) -> Option<u64> {
+        if !self.packet_reader.seek_absgp(None, granule).ok()? {
+            return None;
+        }
+
+        // A page's granule marks the end of its last packet, so the start of what
+        // we decode is that granule minus the decoded frame count
+        let mut decoded = Vec::new();
+        loop {
+            let packet = self.packet_reader.read_packet().ok()??;
+            if let Some(samples) = self.decode_packet(&packet.data) {
+                decoded.extend(samples);
+            }
+            if packet.last_in_page() {
+                // The last page may be end-trimmed, which breaks the arithmetic
+                if packet.last_in_stream() {
+                    return None;
+                }
+                let frames = (decoded.len() / self.channels as usize) as u64;
+                let start = packet.absgp_page().checked_sub(frames)?;
+                self.sample_buffer = decoded;
+                return Some(start);
+            }
+        }
+    }
+
+    fn seek_to(&mut self, position: Duration) -> bool {
+        if self.decoder.reset_state().is_err() {
+            return false;
+        }
+        self.sample_buffer.clear();
+        self.buffer_offset = 0;
+        self.eos = false;
+        self.counter.reset(position);
+
+        // Granule positions include the pre-skip
+        let target = (position.as_secs_f64() * OPUS_RATE as f64).round() as u64 + self.pre_skip;
+        let goal = target.saturating_sub(OPUS_PREROLL);
+        let start = Some(goal)
+            .filter(|g| *g > self.pre_skip)
+            .and_then(|g| self.seek_page(g))
+            .filter(|start| *start <= target);
+
+        let skip_frames = match start {
+            Some(start) => target - start,
+            None => {
+                // Fall back to decoding from the beginning
+                if self.packet_reader.seek_absgp(None, 0).is_err() || self.decoder.reset_state().is_err() {
+                    return false;
+                }
+                self.sample_buffer.clear();
+                target
+            }
+        };
+
+        let skip = skip_frames as usize * self.channels as usize;
+        self.buffer_offset = skip.min(self.sample_buffer.len());
+        self.skip_samples = skip - self.buffer_offset;
+        true
     }
 }
 
@@ -102,6 +265,12 @@ impl Iterator for OpusOggSource {
         if self.eos {
             return None;
         }
+
+        // Stop at the final granule; the last packet may decode past it
+        if self.total_frames.is_some_and(|total| self.counter.frames() >= total) {
+            self.eos = true;
+            return None;
+        }
         
         while self.buffer_offset >= self.sample_buffer.len() {
             self.sample_buffer.clear();
@@ -109,25 +278,11 @@ impl Iterator for OpusOggSource {
             
             match self.packet_reader.read_packet() {
                 Ok(Some(packet)) => {
-                    if packet.data.len() >= 8 && &packet.data[0..8] == b"OpusTags" {
-                        continue;
-                    }
-                    
-                    if packet.data.is_empty() {
-                        continue;
-                    }
-                    
-                    let max_frame_size = 5760;
-                    let output_size = if self.channels == 1 { max_frame_size } else { max_frame_size * 2 };
-                    let mut output = vec![0f32; output_size];
-                    
-                    match self.decoder.decode_float(&packet.data, &mut output, false) {
-                        Ok(samples_decoded) => {
-                            let total_samples = samples_decoded * self.channels as usize;
-                            self.sample_buffer = output[..total_samples].to_vec();
-                            self.current_position_bytes += packet.data.len() as u64;
-                        }
-                        Err(_) => continue,
+                    if let Some(samples) = self.decode_packet(&packet.data) {
+                        self.sample_buffer = samples;
+                        let skipped = self.skip_samples.min(self.sample_buffer.len());
+                        self.buffer_offset = skipped;
+                        self.skip_samples -= skipped;
                     }
                 }
                 Ok(None) | Err(_) => {
@@ -137,13 +292,10 @@ impl Iterator for OpusOggSource {
             }
         }
         
-        if self.buffer_offset < self.sample_buffer.len() {
-            let sample = self.sample_buffer[self.buffer_offset];
-            self.buffer_offset += 1;
-            Some(sample)
-        } else {
-            None
-        }
+        let sample = self.sample_buffer[self.buffer_offset];
+        self.buffer_offset += 1;
+        self.counter.tick();
+        Some(sample)
     }
 }
 
@@ -157,11 +309,12 @@ impl Source for OpusOggSource {
     }
 
     fn sample_rate(&self) -> u32 {
-        self.sample_rate
+        OPUS_RATE
     }
 
     fn total_duration(&self) -> Option<Duration> {
-        self.total_duration
+        self.total_frames
+            .map(|frames| Duration::from_secs_f64(frames as f64 / OPUS_RATE as f64))
     }
 }
 
@@ -174,6 +327,9 @@ struct SymphoniaSource {
     channels: u16,
     sample_rate: u32,
     total_duration: Option<Duration>,
+    time_base: Option<symphonia::core::units::TimeBase>,
+    skip_samples: usize,
+    counter: FrameCounter,
 }
 
 impl SymphoniaSource {
@@ -195,7 +351,7 @@ impl SymphoniaSource {
             .format(&hint, mss, &format_opts, &MetadataOptions::default())
             .map_err(|e| format!("Unsupported format: {}", e))?;
 
-        let mut reader = probed.format;
+        let reader = probed.format;
         
         let track = reader.tracks()
             .iter()
@@ -209,6 +365,7 @@ impl SymphoniaSource {
         
         let total_duration = track.codec_params.n_frames
             .map(|f| Duration::from_secs_f64(f as f64 / sample_rate as f64));
+        let time_base = track.codec_params.time_base;
 
         let decoder = symphonia::default::get_codecs()
             .make(&track.codec_params, &DecoderOptions::default())
@@ -217,17 +374,7 @@ impl SymphoniaSource {
                 format!("Unsupported codec '{}'. Supported: MP3, FLAC, Vorbis, AAC, ALAC, WAV, PCM", codec_name)
             })?;
 
-        if let Some(seek_time) = start_time {
-            let _ = reader.seek(
-                SeekMode::Accurate,
-                SeekTo::Time {
-                    time: Time::from(seek_time.as_secs_f64()),
-                    track_id: Some(track_id),
-                },
-            );
-        }
-
-        Ok(Self {
+        let mut source = Self {
             reader,
             decoder,
             track_id,
@@ -236,7 +383,44 @@ impl SymphoniaSource {
             channels,
             sample_rate,
             total_duration,
-        })
+            time_base,
+            skip_samples: 0,
+            counter: FrameCounter::new(channels, sample_rate),
+        };
+
+        if let Some(seek_time) = start_time {
+            source.seek_to(seek_time);
+        }
+
+        Ok(source)
+    }
+
+    fn seek_to(&mut self, position: Duration) -> bool {
+        let seeked = self.reader.seek(
+            SeekMode::Accurate,
+            SeekTo::Time {
+                time: Time::from(position.as_secs_f64()),
+                track_id: Some(self.track_id),
+            },
+        );
+
+        let Ok(seeked) = seeked else {
+            return false;
+        };
+
+        self.decoder.reset();
+        self.sample_buffer.clear();
+        self.current_sample_offset = 0;
+        self.skip_samples = 0;
+        self.counter.reset(position);
+
+        // The reader lands on a packet boundary; drop the frames before the requested time
+        if let Some(time_base) = self.time_base {
+            let gap = time_base.calc_time(seeked.required_ts.saturating_sub(seeked.actual_ts));
+            let frames = ((gap.seconds as f64 + gap.frac) * self.sample_rate as f64).round() as usize;
+            self.skip_samples = frames * self.channels as usize;
+        }
+        true
     }
 }
 
@@ -264,9 +448,12 @@ impl Iterator for SymphoniaSource {
                         
                         self.sample_buffer.clear();
                         self.sample_buffer.extend_from_slice(sample_buf.samples());
-                        self.current_sample_offset = 0;
-                        
-                        if !self.sample_buffer.is_empty() {
+
+                        let skipped = self.skip_samples.min(self.sample_buffer.len());
+                        self.current_sample_offset = skipped;
+                        self.skip_samples -= skipped;
+
+                        if self.current_sample_offset < self.sample_buffer.len() {
                             break;
                         }
                     }
@@ -278,6 +465,7 @@ impl Iterator for SymphoniaSource {
         if self.current_sample_offset < self.sample_buffer.len() {
             let sample = self.sample_buffer[self.current_sample_offset];
             self.current_sample_offset += 1;
+            self.counter.tick();
             Some(sample)
         } else {
             None
@@ -303,107 +491,236 @@ impl Source for SymphoniaSource {
     }
 }
 
-struct SendWrapper<T>(T);
-unsafe impl<T> Send for SendWrapper<T> {}
-unsafe impl<T> Sync for SendWrapper<T> {}
+/// Sources that can jump to a position in place, without reopening the file.
+trait Rewind {
+    fn rewind(&mut self, position: Duration) -> bool;
+    /// Shared position of the frames pulled so far, following rewinds.
+    fn playhead(&self) -> Arc<Playhead>;
+}
 
-#[derive(Clone)]
-pub struct LevelData {
-    pub peak: f32,
-    pub rms: f32,
-    pub volume: f32,
-    pub last_update: Instant,
+impl Rewind for SymphoniaSource {
+    fn rewind(&mut self, position: Duration) -> bool {
+        self.seek_to(position)
+    }
+
+    fn playhead(&self) -> Arc<Playhead> {
+        Arc::clone(&self.counter.playhead)
+    }
 }
 
-impl Default for LevelData {
-    fn default() -> Self {
-        Self {
-            peak: 0.0,
-            rms: 0.0,
-            volume: 1.0,
-            last_update: Instant::now(),
+impl Rewind for OpusOggSource {
+    fn rewind(&mut self, position: Duration) -> bool {
+        self.seek_to(position)
+    }
+
+    fn playhead(&self) -> Arc<Playhead> {
+        Arc::clone(&self.counter.playhead)
+    }
+}
+
+/// Plays a clip from the in-memory sample cache.
+struct CachedSource {
+    clip: Arc<DecodedClip>,
+    offset: usize,
+    counter: FrameCounter,
+}
+
+impl CachedSource {
+    fn new(clip: Arc<DecodedClip>, start_time: Option<Duration>) -> Self {
+        let counter = FrameCounter::new(clip.channels, clip.sample_rate);
+        let mut source = Self { clip, offset: 0, counter };
+        source.rewind(start_time.unwrap_or_default());
+        source
+    }
+}
+
+impl Iterator for CachedSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let sample = *self.clip.samples.get(self.offset)?;
+        self.offset += 1;
+        self.counter.tick();
+        Some(sample)
+    }
+}
+
+impl Source for CachedSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.clip.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.clip.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(self.clip.duration())
+    }
+}
+
+impl Rewind for CachedSource {
+    fn rewind(&mut self, position: Duration) -> bool {
+        let frames = (position.as_secs_f64() * self.clip.sample_rate as f64).round() as usize;
+        self.offset = (frames * self.clip.channels as usize).min(self.clip.samples.len());
+        self.counter.reset(position);
+        true
+    }
+
+    fn playhead(&self) -> Arc<Playhead> {
+        Arc::clone(&self.counter.playhead)
+    }
+}
+
+enum DecodedSource {
+    Symphonia(SymphoniaSource),
+    Opus(OpusOggSource),
+    Cached(CachedSource),
+}
+
+impl Iterator for DecodedSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::Symphonia(s) => s.next(),
+            Self::Opus(s) => s.next(),
+            Self::Cached(s) => s.next(),
         }
     }
 }
 
-struct LevelMeter<S> {
-    source: S,
-    levels: Arc<Mutex<LevelData>>,
-    window_size: usize,
-    window_samples: Vec<f32>,
-    window_index: usize,
+impl Source for DecodedSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        match self {
+            Self::Symphonia(s) => s.channels(),
+            Self::Opus(s) => s.channels(),
+            Self::Cached(s) => s.channels(),
+        }
+    }
+
+    fn sample_rate(&self) -> u32 {
+        match self {
+            Self::Symphonia(s) => s.sample_rate(),
+            Self::Opus(s) => s.sample_rate(),
+            Self::Cached(s) => s.sample_rate(),
+        }
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        match self {
+            Self::Symphonia(s) => s.total_duration(),
+            Self::Opus(s) => s.total_duration(),
+            Self::Cached(s) => s.total_duration(),
+        }
+    }
 }
 
-impl<S> LevelMeter<S>
-where
-    S: Source<Item = f32>,
-{
-    fn new(source: S, levels: Arc<Mutex<LevelData>>) -> Self {
-        Self {
-            source,
-            levels,
-            window_size: 512,
-            window_samples: vec![0.0; 512],
-            window_index: 0,
+impl Rewind for DecodedSource {
+    fn rewind(&mut self, position: Duration) -> bool {
+        match self {
+            Self::Symphonia(s) => s.rewind(position),
+            Self::Opus(s) => s.rewind(position),
+            Self::Cached(s) => s.rewind(position),
         }
     }
 
-    fn update_levels(&mut self) {
-        if self.window_index < self.window_size {
-            return;
+    fn playhead(&self) -> Arc<Playhead> {
+        match self {
+            Self::Symphonia(s) => s.playhead(),
+            Self::Opus(s) => s.playhead(),
+            Self::Cached(s) => s.playhead(),
         }
+    }
+}
 
-        let peak = self.window_samples
-            .iter()
-            .map(|s| s.abs())
-            .fold(0.0f32, f32::max);
+/// Repeats a region of its source forever. At the loop end (or the end of the
+/// file) the source is rewound inside the same `next()` call, so there is no gap.
+struct Looper<S> {
+    source: S,
+    controls: Arc<InstanceControls>,
+    bounds: Option<(Duration, Option<Duration>)>,
+    start_frame: u64,
+    end_frame: Option<u64>,
+    position_frames: u64,
+    channel_index: u16,
+}
 
-        let rms = (self.window_samples
-            .iter()
-            .map(|s| s * s)
-            .sum::<f32>() / self.window_samples.len() as f32)
-            .sqrt();
+impl<S> Looper<S>
+where
+    S: Source<Item = f32> + Rewind,
+{
+    /// `bounds` is the loop region (end `None` = end of file), `None` disables looping.
+    fn new(source: S, controls: Arc<InstanceControls>, position: Duration, bounds: Option<(Duration, Option<Duration>)>) -> Self {
+        let rate = source.sample_rate() as f64;
+        let to_frames = |d: Duration| (d.as_secs_f64() * rate).round() as u64;
 
-        if let Ok(mut levels) = self.levels.lock() {
-            levels.peak = peak;
-            levels.rms = rms;
-            levels.last_update = Instant::now();
+        Self {
+            start_frame: bounds.map(|(start, _)| to_frames(start)).unwrap_or(0),
+            end_frame: bounds.and_then(|(_, end)| end).map(to_frames),
+            position_frames: to_frames(position),
+            source,
+            controls,
+            bounds,
+            channel_index: 0,
         }
+    }
 
-        self.window_index = 0;
+    fn restart(&mut self) -> Option<()> {
+        let (start, _) = self.bounds?;
+        if !self.source.rewind(start) {
+            return None;
+        }
+        self.position_frames = self.start_frame;
+        self.controls.loop_count.fetch_add(1, Ordering::Relaxed);
+        Some(())
     }
 }
 
-impl<S> Iterator for LevelMeter<S>
+impl<S> Iterator for Looper<S>
 where
-    S: Source<Item = f32>,
+    S: Source<Item = f32> + Rewind,
 {
     type Item = f32;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(sample) = self.source.next() {
-            let amplitude = sample;
-            
-            self.window_samples[self.window_index] = amplitude;
-            self.window_index += 1;
+        if self.channel_index == 0 {
+            if self.end_frame.is_some_and(|end| self.position_frames >= end) {
+                self.restart()?;
+            }
+            self.position_frames += 1;
+        }
 
-            if self.window_index >= self.window_size {
-                self.update_levels();
+        let sample = match self.source.next() {
+            Some(sample) => sample,
+            None if self.bounds.is_some() && self.channel_index == 0 => {
+                // The file ended before the loop end: wrap around immediately
+                self.restart()?;
+                self.position_frames += 1;
+                self.source.next()?
             }
+            None => return None,
+        };
 
-            Some(sample)
-        } else {
-            None
-        }
+        self.channel_index = (self.channel_index + 1) % self.source.channels().max(1);
+        Some(sample)
     }
 }
 
-impl<S> Source for LevelMeter<S>
+impl<S> Source for Looper<S>
 where
-    S: Source<Item = f32>,
+    S: Source<Item = f32> + Rewind,
 {
     fn current_frame_len(&self) -> Option<usize> {
-        self.source.current_frame_len()
+        None
     }
 
     fn channels(&self) -> u16 {
@@ -415,305 +732,2555 @@ where
     }
 
     fn total_duration(&self) -> Option<Duration> {
-        self.source.total_duration()
+        if self.bounds.is_some() {
+            None
+        } else {
+            self.source.total_duration()
+        }
     }
 }
 
-#[derive(Clone, Serialize)]
-pub struct MasterLevelEvent {
-    pub peak: f32,
-    pub rms: f32,
+/// Ends its source exactly at a frame count, used for the out point of a trimmed clip.
+struct TrimEnd<S> {
+    source: S,
+    remaining_samples: Option<u64>,
 }
 
-pub struct MeterManager {
-    app_handle: tauri::AppHandle,
-    active_meters: Arc<Mutex<Vec<(Arc<Mutex<LevelData>>, Arc<Sink>)>>>,
-    master_volume: Arc<Mutex<f32>>,
+impl<S> TrimEnd<S>
+where
+    S: Source<Item = f32>,
+{
+    fn new(source: S, length: Option<Duration>) -> Self {
+        let remaining_samples = length.map(|l| {
+            let frames = (l.as_secs_f64() * source.sample_rate() as f64).round() as u64;
+            frames * source.channels() as u64
+        });
+        Self { source, remaining_samples }
+    }
 }
 
-impl MeterManager {
-    pub fn new(app_handle: tauri::AppHandle, master_volume: Arc<Mutex<f32>>) -> Self {
-        Self {
+impl<S> Iterator for TrimEnd<S>
+where
+    S: Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.remaining_samples.as_mut() {
+            Some(0) => None,
+            Some(remaining) => {
+                *remaining -= 1;
+                self.source.next()
+            }
+            None => self.source.next(),
+        }
+    }
+}
+
+impl<S> Source for TrimEnd<S>
+where
+    S: Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.source.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        match self.remaining_samples {
+            Some(samples) => {
+                let frame_rate = self.source.sample_rate() as f64 * self.source.channels() as f64;
+                Some(Duration::from_secs_f64(samples as f64 / frame_rate.max(1.0)))
+            }
+            None => self.source.total_duration(),
+        }
+    }
+}
+
+/// Live controls shared by every source chain of one instance (main and monitor).
+pub struct InstanceControls {
+    /// Loudness normalization gain, fixed when the instance starts.
+    pub normalization_gain: f32,
+    /// Requested stop fade length in ms, 0 when no fade-out stop is pending.
+    stop_fade_ms: AtomicU64,
+    /// Number of times a looping instance has wrapped around.
+    pub loop_count: AtomicU32,
+    effects: Mutex<EffectsSettings>,
+    /// Bumped on every effects change so chains only relock when needed.
+    effects_revision: AtomicU64,
+    /// Pitch shift in semitones and tempo factor, stored as `f32` bits.
+    pitch_semitones: AtomicU32,
+    tempo: AtomicU32,
+}
+
+impl InstanceControls {
+    pub fn new(normalization_gain: f32, effects: EffectsSettings, pitch_semitones: f32, tempo: f32) -> Self {
+        Self {
+            normalization_gain,
+            stop_fade_ms: AtomicU64::new(0),
+            loop_count: AtomicU32::new(0),
+            effects: Mutex::new(effects),
+            effects_revision: AtomicU64::new(0),
+            pitch_semitones: AtomicU32::new(pitch_semitones.to_bits()),
+            tempo: AtomicU32::new(tempo.to_bits()),
+        }
+    }
+
+    pub fn pitch_semitones(&self) -> f32 {
+        f32::from_bits(self.pitch_semitones.load(Ordering::Relaxed))
+    }
+
+    pub fn pitch_ratio(&self) -> f32 {
+        2f32.powf(self.pitch_semitones() / 12.0)
+    }
+
+    pub fn tempo(&self) -> f32 {
+        f32::from_bits(self.tempo.load(Ordering::Relaxed))
+    }
+
+    pub fn set_rate(&self, pitch_semitones: f32, tempo: f32) {
+        self.pitch_semitones.store(pitch_semitones.to_bits(), Ordering::Relaxed);
+        self.tempo.store(tempo.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn request_stop_fade(&self, fade_ms: u64) {
+        self.stop_fade_ms.store(fade_ms.max(1), Ordering::Relaxed);
+    }
+
+    pub fn set_effects(&self, effects: EffectsSettings) {
+        if let Ok(mut current) = self.effects.lock() {
+            *current = effects;
+            self.effects_revision.fetch_add(1, Ordering::Release);
+        }
+    }
+}
+
+/// Per-button effect chain (filters, EQ, compressor, gain trim). Bypassed while
+/// the settings are neutral and rebuilt in place when they change.
+struct Effects<S> {
+    source: S,
+    controls: Arc<InstanceControls>,
+    chain: Option<EffectChain>,
+    revision: Option<u64>,
+    channel_index: u16,
+}
+
+impl<S> Effects<S>
+where
+    S: Source<Item = f32>,
+{
+    fn new(source: S, controls: Arc<InstanceControls>) -> Self {
+        let mut effects = Self {
+            source,
+            controls,
+            chain: None,
+            revision: None,
+            channel_index: 0,
+        };
+        effects.refresh();
+        effects
+    }
+
+    fn refresh(&mut self) {
+        let revision = self.controls.effects_revision.load(Ordering::Acquire);
+        if self.revision == Some(revision) {
+            return;
+        }
+        let Ok(settings) = self.controls.effects.lock() else {
+            return;
+        };
+        self.revision = Some(revision);
+
+        if settings.is_neutral() {
+            self.chain = None;
+        } else if let Some(chain) = self.chain.as_mut() {
+            chain.update(&settings);
+        } else {
+            self.chain = Some(EffectChain::new(&settings, self.source.channels(), self.source.sample_rate()));
+        }
+    }
+}
+
+impl<S> Iterator for Effects<S>
+where
+    S: Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Only swap parameters between frames so channels stay in step
+        if self.channel_index == 0 {
+            self.refresh();
+        }
+        let sample = self.source.next()?;
+        let channel = self.channel_index as usize;
+        self.channel_index = (self.channel_index + 1) % self.source.channels().max(1);
+
+        Some(match self.chain.as_mut() {
+            Some(chain) => chain.process(channel, sample),
+            None => sample,
+        })
+    }
+}
+
+impl<S> Source for Effects<S>
+where
+    S: Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.source.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.source.total_duration()
+    }
+}
+
+/// Gain envelope for fade-in, fade-out at the end of the region, and fade-on-stop,
+/// on top of the instance's normalization gain.
+/// Ends the source once a stop fade has completed.
+struct Envelope<S> {
+    source: S,
+    controls: Arc<InstanceControls>,
+    channel_index: u16,
+    position_frames: u64,
+    fade_in_frames: u64,
+    fade_out: Option<(u64, u64)>,
+    stop_fade: Option<(u64, u64)>,
+    gain: f32,
+}
+
+impl<S> Envelope<S>
+where
+    S: Source<Item = f32>,
+{
+    /// `position` is where the source starts inside the region, `region_length` is `None` when unknown.
+    fn new(
+        source: S,
+        controls: Arc<InstanceControls>,
+        position: Duration,
+        region_length: Option<Duration>,
+        fade_in: Duration,
+        fade_out: Duration,
+    ) -> Self {
+        let rate = source.sample_rate() as f64;
+        let to_frames = |d: Duration| (d.as_secs_f64() * rate).round() as u64;
+
+        let fade_out = region_length
+            .filter(|_| !fade_out.is_zero())
+            .map(|len| (to_frames(len.saturating_sub(fade_out)), to_frames(fade_out)));
+
+        Self {
+            source,
+            controls,
+            channel_index: 0,
+            position_frames: to_frames(position),
+            fade_in_frames: to_frames(fade_in),
+            fade_out,
+            stop_fade: None,
+            gain: 1.0,
+        }
+    }
+
+    fn compute_gain(&mut self) -> Option<f32> {
+        if self.stop_fade.is_none() {
+            let fade_ms = self.controls.stop_fade_ms.load(Ordering::Relaxed);
+            if fade_ms > 0 {
+                let frames = (fade_ms as f64 / 1000.0 * self.source.sample_rate() as f64).round() as u64;
+                self.stop_fade = Some((0, frames.max(1)));
+            }
+        }
+
+        let mut gain = self.controls.normalization_gain;
+
+        if self.position_frames < self.fade_in_frames {
+            gain *= self.position_frames as f32 / self.fade_in_frames as f32;
+        }
+
+        if let Some((start, length)) = self.fade_out {
+            if self.position_frames >= start {
+                let done = (self.position_frames - start).min(length);
+                gain *= 1.0 - done as f32 / length as f32;
+            }
+        }
+
+        if let Some((done, length)) = self.stop_fade.as_mut() {
+            if *done >= *length {
+                return None;
+            }
+            gain *= 1.0 - *done as f32 / *length as f32;
+            *done += 1;
+        }
+
+        Some(gain)
+    }
+}
+
+impl<S> Iterator for Envelope<S>
+where
+    S: Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Gain is computed once per frame so all channels move together
+        if self.channel_index == 0 {
+            self.gain = self.compute_gain()?;
+            self.position_frames += 1;
+        }
+        let sample = self.source.next()?;
+        self.channel_index = (self.channel_index + 1) % self.source.channels().max(1);
+
+        Some(sample * self.gain)
+    }
+}
+
+impl<S> Source for Envelope<S>
+where
+    S: Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.source.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.source.total_duration()
+    }
+}
+
+const GRAIN_MS: f64 = 40.0;
+
+struct Grain {
+    position: f64,
+    step: f64,
+    age: usize,
+}
+
+/// Granular pitch shift and time stretch. Two Hann-windowed grains overlap by half;
+/// each reads the input at the pitch ratio, while grain start points advance at the tempo.
+/// At pitch 0 / tempo 1 the grains line up and the input passes through unchanged.
+struct Varispeed<S> {
+    source: S,
+    controls: Arc<InstanceControls>,
+    channels: usize,
+    window: Vec<f32>,
+    input: VecDeque<f32>,
+    /// Frame index of the first buffered input frame.
+    input_start: u64,
+    source_done: bool,
+    analysis: f64,
+    grains: Vec<Grain>,
+    output_frames: u64,
+    frame: Vec<f32>,
+    frame_offset: usize,
+}
+
+impl<S> Varispeed<S>
+where
+    S: Source<Item = f32>,
+{
+    fn new(source: S, controls: Arc<InstanceControls>) -> Self {
+        let channels = source.channels().max(1) as usize;
+        let grain_frames = ((source.sample_rate() as f64 * GRAIN_MS / 1000.0) as usize / 2 * 2).max(2);
+        let window = (0..grain_frames)
+            .map(|n| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * n as f32 / grain_frames as f32).cos())
+            .collect();
+
+        let step = controls.pitch_ratio() as f64;
+        Self {
+            source,
+            controls,
+            channels,
+            window,
+            input: VecDeque::new(),
+            input_start: 0,
+            source_done: false,
+            analysis: 0.0,
+            // Starts half-way through a grain so the first frames are not faded in
+            grains: vec![Grain { position: 0.0, step, age: grain_frames / 2 }],
+            output_frames: 0,
+            frame: vec![0.0; channels],
+            frame_offset: channels,
+        }
+    }
+
+    fn input_end(&self) -> u64 {
+        self.input_start + (self.input.len() / self.channels) as u64
+    }
+
+    /// Buffers input up to (and including) `frame`. Returns `false` past the end of the source.
+    fn fill_to(&mut self, frame: u64) -> bool {
+        while !self.source_done && self.input_end() <= frame {
+            for _ in 0..self.channels {
+                match self.source.next() {
+                    Some(sample) => self.input.push_back(sample),
+                    None => {
+                        // Drop a partial frame so channels stay aligned
+                        let partial = self.input.len() % self.channels;
+                        self.input.truncate(self.input.len() - partial);
+                        self.source_done = true;
+                        break;
+                    }
+                }
+            }
+        }
+        frame < self.input_end()
+    }
+
+    fn input_sample(&self, frame: u64, channel: usize) -> f32 {
+        if frame < self.input_start || frame >= self.input_end() {
+            return 0.0;
+        }
+        self.input[(frame - self.input_start) as usize * self.channels + channel]
+    }
+
+    /// Produces the next output frame, or `false` once everything has been played.
+    fn render_frame(&mut self) -> bool {
+        let grain_frames = self.window.len();
+        if self.output_frames.is_multiple_of(grain_frames as u64 / 2) {
+            self.grains.retain(|g| g.age < grain_frames);
+            self.grains.push(Grain {
+                position: self.analysis,
+                step: self.controls.pitch_ratio() as f64,
+                age: 0,
+            });
+        }
+
+        self.frame.iter_mut().for_each(|s| *s = 0.0);
+        let mut any_input = false;
+        for i in 0..self.grains.len() {
+            let (position, age) = (self.grains[i].position, self.grains[i].age);
+            if age >= grain_frames {
+                continue;
+            }
+            let index = position.floor() as u64;
+            let fraction = (position - position.floor()) as f32;
+            any_input |= self.fill_to(index + 1) || index < self.input_end();
+
+            let gain = self.window[age];
+            for channel in 0..self.channels {
+                let a = self.input_sample(index, channel);
+                let b = self.input_sample(index + 1, channel);
+                self.frame[channel] += (a + (b - a) * fraction) * gain;
+            }
+
+            let grain = &mut self.grains[i];
+            grain.position += grain.step;
+            grain.age += 1;
+        }
+
+        self.analysis += self.controls.tempo() as f64;
+        self.output_frames += 1;
+
+        // Forget input no grain can reach anymore
+        let oldest = self.grains.iter()
+            .filter(|g| g.age < grain_frames)
+            .map(|g| g.position)
+            .fold(self.analysis, f64::min)
+            .floor() as u64;
+        while self.input_start < oldest && !self.input.is_empty() {
+            self.input.drain(..self.channels);
+            self.input_start += 1;
+        }
+
+        any_input || !self.source_done
+    }
+}
+
+impl<S> Iterator for Varispeed<S>
+where
+    S: Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.frame_offset >= self.channels {
+            if !self.render_frame() {
+                return None;
+            }
+            self.frame_offset = 0;
+        }
+        let sample = self.frame[self.frame_offset];
+        self.frame_offset += 1;
+        Some(sample)
+    }
+}
+
+impl<S> Source for Varispeed<S>
+where
+    S: Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels as u16
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+struct SendWrapper<T>(T);
+unsafe impl<T> Send for SendWrapper<T> {}
+unsafe impl<T> Sync for SendWrapper<T> {}
+
+#[derive(Clone)]
+pub struct LevelData {
+    pub peak: f32,
+    pub rms: f32,
+    pub volume: f32,
+    pub last_update: Instant,
+}
+
+impl Default for LevelData {
+    fn default() -> Self {
+        Self {
+            peak: 0.0,
+            rms: 0.0,
+            volume: 1.0,
+            last_update: Instant::now(),
+        }
+    }
+}
+
+struct LevelMeter<S> {
+    source: S,
+    levels: Arc<Mutex<LevelData>>,
+    window_size: usize,
+    window_samples: Vec<f32>,
+    window_index: usize,
+}
+
+impl<S> LevelMeter<S>
+where
+    S: Source<Item = f32>,
+{
+    fn new(source: S, levels: Arc<Mutex<LevelData>>) -> Self {
+        Self {
+            source,
+            levels,
+            window_size: 512,
+            window_samples: vec![0.0; 512],
+            window_index: 0,
+        }
+    }
+
+    fn update_levels(&mut self) {
+        if self.window_index < self.window_size {
+            return;
+        }
+
+        let peak = self.window_samples
+            .iter()
+            .map(|s| s.abs())
+            .fold(0.0f32, f32::max);
+
+        let rms = (self.window_samples
+            .iter()
+            .map(|s| s * s)
+            .sum::<f32>() / self.window_samples.len() as f32)
+            .sqrt();
+
+        if let Ok(mut levels) = self.levels.lock() {
+            levels.peak = peak;
+            levels.rms = rms;
+            levels.last_update = Instant::now();
+        }
+
+        self.window_index = 0;
+    }
+}
+
+impl<S> Iterator for LevelMeter<S>
+where
+    S: Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(sample) = self.source.next() {
+            let amplitude = sample;
+            
+            self.window_samples[self.window_index] = amplitude;
+            self.window_index += 1;
+
+            if self.window_index >= self.window_size {
+                self.update_levels();
+            }
+
+            Some(sample)
+        } else {
+            None
+        }
+    }
+}
+
+impl<S> Source for LevelMeter<S>
+where
+    S: Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.source.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.source.total_duration()
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DuckingSettings {
+    pub enabled: bool,
+    pub amount_db: f32,
+    pub attack_ms: f32,
+    pub release_ms: f32,
+    pub duck_mic: bool,
+}
+
+impl Default for DuckingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            amount_db: 12.0,
+            attack_ms: 50.0,
+            release_ms: 400.0,
+            duck_mic: true,
+        }
+    }
+}
+
+/// Shared ducking state: the meter loop flips `active`, every `Ducker` follows it.
+pub struct DuckControl {
+    active: AtomicBool,
+    settings: Mutex<DuckingSettings>,
+}
+
+impl DuckControl {
+    pub fn new() -> Self {
+        Self {
+            active: AtomicBool::new(false),
+            settings: Mutex::new(DuckingSettings::default()),
+        }
+    }
+
+    pub fn set_active(&self, active: bool) {
+        self.active.store(active, Ordering::Relaxed);
+    }
+
+    pub fn update_settings(&self, settings: DuckingSettings) {
+        if let Ok(mut current) = self.settings.lock() {
+            *current = settings;
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum DuckTarget {
+    Microphone,
+    Instance { duckable: bool },
+}
+
+/// Gain envelope that attenuates its source while a soundboard instance plays.
+/// The gain is smoothed per frame, so attack and release are sample-accurate.
+struct Ducker<S> {
+    source: S,
+    control: Arc<DuckControl>,
+    target_kind: DuckTarget,
+    gain: f32,
+    target: f32,
+    attack_coeff: f32,
+    release_coeff: f32,
+    channel_index: u16,
+    samples_until_refresh: usize,
+}
+
+impl<S> Ducker<S>
+where
+    S: Source<Item = f32>,
+{
+    fn new(source: S, control: Arc<DuckControl>, target_kind: DuckTarget) -> Self {
+        let mut ducker = Self {
+            source,
+            control,
+            target_kind,
+            gain: 1.0,
+            target: 1.0,
+            attack_coeff: 0.0,
+            release_coeff: 0.0,
+            channel_index: 0,
+            samples_until_refresh: 0,
+        };
+        ducker.refresh();
+        ducker.gain = ducker.target;
+        ducker
+    }
+
+    fn refresh(&mut self) {
+        let Ok(settings) = self.control.settings.lock().map(|s| s.clone()) else {
+            return;
+        };
+
+        let applies = match self.target_kind {
+            DuckTarget::Microphone => settings.duck_mic,
+            DuckTarget::Instance { duckable } => duckable,
+        };
+
+        self.target = if settings.enabled && applies && self.control.active.load(Ordering::Relaxed) {
+            10f32.powf(-settings.amount_db.max(0.0) / 20.0)
+        } else {
+            1.0
+        };
+
+        let frame_rate = self.source.sample_rate().max(1) as f32;
+        let coeff = |ms: f32| (-1.0 / (ms.max(0.1) / 1000.0 * frame_rate)).exp();
+        self.attack_coeff = coeff(settings.attack_ms);
+        self.release_coeff = coeff(settings.release_ms);
+        self.samples_until_refresh = 256;
+    }
+}
+
+impl<S> Iterator for Ducker<S>
+where
+    S: Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let sample = self.source.next()?;
+
+        if self.samples_until_refresh == 0 {
+            self.refresh();
+        }
+        self.samples_until_refresh -= 1;
+
+        // Advance the envelope once per frame so every channel gets the same gain
+        if self.channel_index == 0 {
+            let coeff = if self.target < self.gain { self.attack_coeff } else { self.release_coeff };
+            self.gain = self.target + (self.gain - self.target) * coeff;
+        }
+        self.channel_index = (self.channel_index + 1) % self.source.channels().max(1);
+
+        Some(sample * self.gain)
+    }
+}
+
+impl<S> Source for Ducker<S>
+where
+    S: Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.source.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.source.total_duration()
+    }
+}
+
+/// Reads captured microphone samples from a shared buffer. Outputs silence on
+/// underrun and never ends, so the mic stays a permanent source on the output.
+struct MicSource {
+    buffer: Arc<Mutex<VecDeque<f32>>>,
+    chunk: Vec<f32>,
+    chunk_offset: usize,
+    channels: u16,
+    sample_rate: u32,
+}
+
+impl Iterator for MicSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.chunk_offset >= self.chunk.len() {
+            self.chunk.clear();
+            self.chunk_offset = 0;
+
+            if let Ok(mut buffer) = self.buffer.lock() {
+                // Always take whole frames so channels never get swapped
+                let available = buffer.len().min(512);
+                let take = available - available % self.channels as usize;
+                self.chunk.extend(buffer.drain(..take));
+            }
+
+            if self.chunk.is_empty() {
+                self.chunk.resize(self.channels as usize, 0.0);
+            }
+        }
+
+        let sample = self.chunk[self.chunk_offset];
+        self.chunk_offset += 1;
+        Some(sample)
+    }
+}
+
+impl Source for MicSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+fn build_capture_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    buffer: Arc<Mutex<VecDeque<f32>>>,
+) -> Result<cpal::Stream, String>
+where
+    T: cpal::SizedSample,
+    f32: cpal::FromSample<T>,
+{
+    let channels = config.channels as usize;
+    // Cap buffered audio at ~100 ms so a slow consumer never turns into growing latency
+    let max_buffered = (config.sample_rate.0 as usize / 10) * channels;
+
+    device.build_input_stream(
+        config,
+        move |data: &[T], _: &cpal::InputCallbackInfo| {
+            if let Ok(mut buffer) = buffer.lock() {
+                buffer.extend(data.iter().map(|s| <f32 as cpal::FromSample<T>>::from_sample_(*s)));
+                if buffer.len() > max_buffered {
+                    let excess = buffer.len() - max_buffered;
+                    buffer.drain(..excess + (channels - excess % channels) % channels);
+                }
+            }
+        },
+        |e| eprintln!("Microphone capture error: {}", e),
+        None,
+    ).map_err(|e| format!("Failed to open input stream: {}", e))
+}
+
+fn build_playback_stream<T, S>(device: &cpal::Device, config: &cpal::StreamConfig, mut source: S) -> Result<cpal::Stream, String>
+where
+    T: cpal::SizedSample + cpal::FromSample<f32>,
+    S: Source<Item = f32> + Send + 'static,
+{
+    device.build_output_stream(
+        config,
+        move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+            for sample in data.iter_mut() {
+                *sample = T::from_sample_(source.next().unwrap_or(0.0));
+            }
+        },
+        |e| eprintln!("Output stream error: {}", e),
+        None,
+    ).map_err(|e| format!("Failed to open output stream: {}", e))
+}
+
+/// Captures an input device and plays it on the main output next to the soundboard.
+pub struct MicPassthrough {
+    pub device_name: String,
+    _stream: SendWrapper<cpal::Stream>,
+    buffer: Arc<Mutex<VecDeque<f32>>>,
+    channels: u16,
+    sample_rate: u32,
+    sink: Arc<Sink>,
+    pub levels: Arc<Mutex<LevelData>>,
+    ducking: Arc<DuckControl>,
+}
+
+impl MicPassthrough {
+    pub fn start(host: &cpal::Host, device_name: &str, bus: &MasterBus, ducking: Arc<DuckControl>) -> Result<Self, String> {
+        let device = if device_name == "Default" {
+            host.default_input_device()
+        } else {
+            host.input_devices().map_err(|e| e.to_string())?
+                .find(|d| d.name().map(|n| n == device_name).unwrap_or(false))
+        }.ok_or("Input device not found")?;
+
+        let supported = device.default_input_config().map_err(|e| e.to_string())?;
+        let config: cpal::StreamConfig = supported.config();
+        let buffer = Arc::new(Mutex::new(VecDeque::new()));
+
+        let stream = match supported.sample_format() {
+            cpal::SampleFormat::F32 => build_capture_stream::<f32>(&device, &config, Arc::clone(&buffer)),
+            cpal::SampleFormat::I16 => build_capture_stream::<i16>(&device, &config, Arc::clone(&buffer)),
+            cpal::SampleFormat::U16 => build_capture_stream::<u16>(&device, &config, Arc::clone(&buffer)),
+            cpal::SampleFormat::I32 => build_capture_stream::<i32>(&device, &config, Arc::clone(&buffer)),
+            other => Err(format!("Unsupported input sample format: {}", other)),
+        }?;
+        stream.play().map_err(|e| format!("Failed to start input stream: {}", e))?;
+
+        let levels = Arc::new(Mutex::new(LevelData::default()));
+        let sink = Self::create_sink(bus, &buffer, config.channels, config.sample_rate.0, &levels, &ducking)?;
+
+        Ok(Self {
+            device_name: device_name.to_string(),
+            _stream: SendWrapper(stream),
+            buffer,
+            channels: config.channels,
+            sample_rate: config.sample_rate.0,
+            sink,
+            levels,
+            ducking,
+        })
+    }
+
+    fn create_sink(
+        bus: &MasterBus,
+        buffer: &Arc<Mutex<VecDeque<f32>>>,
+        channels: u16,
+        sample_rate: u32,
+        levels: &Arc<Mutex<LevelData>>,
+        ducking: &Arc<DuckControl>,
+    ) -> Result<Arc<Sink>, String> {
+        let sink = bus.new_sink();
+        let source = MicSource {
+            buffer: Arc::clone(buffer),
+            chunk: Vec::new(),
+            chunk_offset: 0,
+            channels,
+            sample_rate,
+        };
+        let ducked = Ducker::new(source, Arc::clone(ducking), DuckTarget::Microphone);
+        sink.append(LevelMeter::new(ducked, Arc::clone(levels)));
+        Ok(Arc::new(sink))
+    }
+
+    /// Moves the passthrough to another output, e.g. after the main device changed.
+    pub fn attach(&mut self, bus: &MasterBus) -> Result<(), String> {
+        let volume = self.sink.volume();
+        let sink = Self::create_sink(bus, &self.buffer, self.channels, self.sample_rate, &self.levels, &self.ducking)?;
+        sink.set_volume(volume);
+        self.sink.stop();
+        self.sink = sink;
+        Ok(())
+    }
+
+    pub fn apply_gain(&self, gain: f32, muted: bool) {
+        let volume = if muted { 0.0 } else { gain };
+        self.sink.set_volume(volume);
+        if let Ok(mut levels) = self.levels.lock() {
+            levels.volume = volume;
+        }
+    }
+}
+
+impl Drop for MicPassthrough {
+    fn drop(&mut self) {
+        self.sink.stop();
+    }
+}
+
+/// Brickwall limiter settings shared by every master bus.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LimiterSettings {
+    pub enabled: bool,
+    pub ceiling_db: f32,
+    pub release_ms: f32,
+}
+
+impl Default for LimiterSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            ceiling_db: -1.0,
+            release_ms: 150.0,
+        }
+    }
+}
+
+/// Post-limiter levels of a master bus. `gain_reduction_db` is positive while limiting.
+#[derive(Clone)]
+pub struct BusLevels {
+    pub peak: f32,
+    pub rms: f32,
+    pub gain_reduction_db: f32,
+    pub last_update: Instant,
+}
+
+impl Default for BusLevels {
+    fn default() -> Self {
+        Self {
+            peak: 0.0,
+            rms: 0.0,
+            gain_reduction_db: 0.0,
+            last_update: Instant::now(),
+        }
+    }
+}
+
+const LIMITER_LOOKAHEAD_MS: u32 = 5;
+const BUS_WINDOW_FRAMES: usize = 512;
+
+/// Look-ahead brickwall limiter over the summed bus. The signal is delayed by the
+/// look-ahead so the gain is already down when a peak reaches the output. Never
+/// ends: an empty mixer is played as silence so the bus stays attached to the device.
+struct Limiter<S> {
+    source: S,
+    channels: usize,
+    sample_rate: u32,
+    settings: Arc<Mutex<LimiterSettings>>,
+    ceiling: f32,
+    release_coeff: f32,
+    lookahead: usize,
+    delay: VecDeque<f32>,
+    /// Sliding minimum of the required gain, as (frame index, gain).
+    hold: VecDeque<(u64, f32)>,
+    /// Last `lookahead` released gains, averaged to smooth the attack.
+    smoothing: VecDeque<f32>,
+    smoothing_sum: f64,
+    release_gain: f32,
+    frame_index: u64,
+    frame: Vec<f32>,
+    frame_offset: usize,
+    levels: Arc<Mutex<BusLevels>>,
+    window_peak: f32,
+    window_squares: f32,
+    window_min_gain: f32,
+    window_frames: usize,
+}
+
+impl<S> Limiter<S>
+where
+    S: Source<Item = f32>,
+{
+    fn new(source: S, settings: Arc<Mutex<LimiterSettings>>, levels: Arc<Mutex<BusLevels>>) -> Self {
+        let channels = source.channels().max(1) as usize;
+        let sample_rate = source.sample_rate();
+        let lookahead = ((sample_rate * LIMITER_LOOKAHEAD_MS / 1000) as usize).max(1);
+
+        let mut limiter = Self {
+            source,
+            channels,
+            sample_rate,
+            settings,
+            ceiling: 1.0,
+            release_coeff: 0.0,
+            lookahead,
+            delay: VecDeque::from(vec![0.0; (lookahead - 1) * channels]),
+            hold: VecDeque::new(),
+            smoothing: VecDeque::from(vec![1.0; lookahead]),
+            smoothing_sum: lookahead as f64,
+            release_gain: 1.0,
+            frame_index: 0,
+            frame: vec![0.0; channels],
+            frame_offset: channels,
+            levels,
+            window_peak: 0.0,
+            window_squares: 0.0,
+            window_min_gain: 1.0,
+            window_frames: 0,
+        };
+        limiter.refresh_settings();
+        limiter
+    }
+
+    fn refresh_settings(&mut self) {
+        let settings = self.settings.lock().map(|s| *s).unwrap_or_default();
+        // A disabled limiter keeps its delay line so toggling it never clicks
+        self.ceiling = if settings.enabled {
+            10f32.powf(settings.ceiling_db.min(0.0) / 20.0)
+        } else {
+            f32::INFINITY
+        };
+        let release_samples = settings.release_ms.max(1.0) / 1000.0 * self.sample_rate as f32;
+        self.release_coeff = (-1.0 / release_samples).exp();
+    }
+
+    fn process_frame(&mut self) {
+        let mut input_peak = 0.0f32;
+        for _ in 0..self.channels {
+            let sample = self.source.next().unwrap_or(0.0);
+            input_peak = input_peak.max(sample.abs());
+            self.delay.push_back(sample);
+        }
+
+        let required = if input_peak > self.ceiling { self.ceiling / input_peak } else { 1.0 };
+        while self.hold.back().is_some_and(|&(_, g)| g >= required) {
+            self.hold.pop_back();
+        }
+        self.hold.push_back((self.frame_index, required));
+        while self.hold.front().is_some_and(|&(i, _)| i + self.lookahead as u64 <= self.frame_index) {
+            self.hold.pop_front();
+        }
+        let held = self.hold.front().map(|&(_, g)| g).unwrap_or(1.0);
+
+        // Instant attack, exponential release, then a moving average over the
+        // look-ahead so the gain ramps down instead of stepping
+        self.release_gain = if held < self.release_gain {
+            held
+        } else {
+            held + (self.release_gain - held) * self.release_coeff
+        };
+        self.smoothing.push_back(self.release_gain);
+        self.smoothing_sum += self.release_gain as f64;
+        self.smoothing_sum -= self.smoothing.pop_front().unwrap_or(1.0) as f64;
+        let gain = (self.smoothing_sum / self.lookahead as f64).min(1.0) as f32;
+
+        for i in 0..self.channels {
+            let sample = self.delay.pop_front().unwrap_or(0.0) * gain;
+            let sample = sample.clamp(-self.ceiling, self.ceiling);
+            self.window_peak = self.window_peak.max(sample.abs());
+            self.window_squares += sample * sample;
+            self.frame[i] = sample;
+        }
+        self.window_min_gain = self.window_min_gain.min(gain);
+        self.frame_index += 1;
+        self.window_frames += 1;
+
+        if self.window_frames >= BUS_WINDOW_FRAMES {
+            if let Ok(mut levels) = self.levels.lock() {
+                levels.peak = self.window_peak;
+                levels.rms = (self.window_squares / (self.window_frames * self.channels) as f32).sqrt();
+                levels.gain_reduction_db = -20.0 * self.window_min_gain.max(1e-6).log10();
+                levels.last_update = Instant::now();
+            }
+            self.window_peak = 0.0;
+            self.window_squares = 0.0;
+            self.window_min_gain = 1.0;
+            self.window_frames = 0;
+            // Smoothing sum is recomputed from time to time to keep float drift away
+            self.smoothing_sum = self.smoothing.iter().map(|g| *g as f64).sum();
+            self.refresh_settings();
+        }
+    }
+}
+
+impl<S> Iterator for Limiter<S>
+where
+    S: Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.frame_offset >= self.channels {
+            self.process_frame();
+            self.frame_offset = 0;
+        }
+        let sample = self.frame[self.frame_offset];
+        self.frame_offset += 1;
+        Some(sample)
+    }
+}
+
+impl<S> Source for Limiter<S>
+where
+    S: Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels as u16
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// Per-device stream format; `None` keeps the device default.
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputConfig {
+    pub sample_rate: Option<u32>,
+    /// Frames per callback.
+    pub buffer_size: Option<u32>,
+}
+
+fn output_stream_config(device: &cpal::Device, requested: OutputConfig) -> Result<(cpal::StreamConfig, cpal::SampleFormat), String> {
+    let default = device.default_output_config().map_err(|e| e.to_string())?;
+    let supported = match requested.sample_rate {
+        Some(rate) if rate != default.sample_rate().0 => device
+            .supported_output_configs()
+            .map_err(|e| e.to_string())?
+            .filter(|range| (range.min_sample_rate().0..=range.max_sample_rate().0).contains(&rate))
+            // Stay as close to the default layout as the device allows
+            .max_by_key(|range| (range.channels() == default.channels(), range.sample_format() == default.sample_format()))
+            .map(|range| range.with_sample_rate(cpal::SampleRate(rate)))
+            .ok_or(format!("The device does not support {} Hz", rate))?,
+        _ => default,
+    };
+
+    let mut config = supported.config();
+    if let Some(frames) = requested.buffer_size {
+        if let cpal::SupportedBufferSize::Range { min, max } = supported.buffer_size() {
+            if !(*min..=*max).contains(&frames) {
+                return Err(format!("Buffer size must be between {} and {} frames", min, max));
+            }
+        }
+        config.buffer_size = cpal::BufferSize::Fixed(frames);
+    }
+    Ok((config, supported.sample_format()))
+}
+
+/// One output device's mix: every sink on the device is summed here and goes
+/// through the limiter before reaching the stream.
+#[derive(Clone)]
+pub struct MasterBus {
+    mixer: Arc<DynamicMixerController<f32>>,
+    pub sample_rate: u32,
+    pub levels: Arc<Mutex<BusLevels>>,
+}
+
+impl MasterBus {
+    /// Opens `device` and starts mixing into it. The bus plays for as long as the returned stream lives.
+    fn start(
+        device: &cpal::Device,
+        requested: OutputConfig,
+        settings: Arc<Mutex<LimiterSettings>>,
+    ) -> Result<(cpal::Stream, Self), String> {
+        let (config, format) = output_stream_config(device, requested)?;
+        let (channels, sample_rate) = (config.channels, config.sample_rate.0);
+        let (mixer, output) = dynamic_mixer::mixer::<f32>(channels, sample_rate);
+        let levels = Arc::new(Mutex::new(BusLevels::default()));
+        let limiter = Limiter::new(output, settings, Arc::clone(&levels));
+
+        let stream = match format {
+            cpal::SampleFormat::F32 => build_playback_stream::<f32, _>(device, &config, limiter),
+            cpal::SampleFormat::I16 => build_playback_stream::<i16, _>(device, &config, limiter),
+            cpal::SampleFormat::U16 => build_playback_stream::<u16, _>(device, &config, limiter),
+            cpal::SampleFormat::I32 => build_playback_stream::<i32, _>(device, &config, limiter),
+            other => Err(format!("Unsupported output sample format: {}", other)),
+        }?;
+        stream.play().map_err(|e| e.to_string())?;
+        Ok((stream, Self { mixer, sample_rate, levels }))
+    }
+
+    fn same_as(&self, other: &MasterBus) -> bool {
+        Arc::ptr_eq(&self.mixer, &other.mixer)
+    }
+
+    /// The output callback stopped pulling from the bus, i.e. its device is gone.
+    fn is_stale(&self) -> bool {
+        self.levels.lock().map_or(true, |l| l.last_update.elapsed() >= DEVICE_STALE_AFTER)
+    }
+
+    /// Creates a sink that plays into this bus. Its queue leaves the mix when the sink is dropped.
+    pub fn new_sink(&self) -> Sink {
+        let (sink, queue) = Sink::new_idle();
+        self.mixer.add(queue);
+        sink
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct MasterLevelEvent {
+    pub peak: f32,
+    pub rms: f32,
+    pub gain_reduction_db: f32,
+}
+
+/// Level data, the sink it belongs to, and whether it triggers ducking.
+type MeterEntry = (Arc<Mutex<LevelData>>, Arc<Sink>, bool);
+
+pub struct MeterManager {
+    app_handle: tauri::AppHandle,
+    active_meters: Arc<Mutex<Vec<MeterEntry>>>,
+    input_meter: Arc<Mutex<Option<Arc<Mutex<LevelData>>>>>,
+    master_bus: Arc<Mutex<Option<Arc<Mutex<BusLevels>>>>>,
+    ducking: Arc<DuckControl>,
+}
+
+impl MeterManager {
+    pub fn new(app_handle: tauri::AppHandle, ducking: Arc<DuckControl>) -> Self {
+        Self {
             app_handle,
             active_meters: Arc::new(Mutex::new(Vec::new())),
-            master_volume,
+            input_meter: Arc::new(Mutex::new(None)),
+            master_bus: Arc::new(Mutex::new(None)),
+            ducking,
+        }
+    }
+
+    pub fn add_meter(&self, meter: Arc<Mutex<LevelData>>, sink: Arc<Sink>, triggers_ducking: bool) {
+        // Duck right away instead of waiting for the next monitoring tick
+        if triggers_ducking {
+            self.ducking.set_active(true);
+        }
+        if let Ok(mut meters) = self.active_meters.lock() {
+            meters.push((meter, sink, triggers_ducking));
+        }
+    }
+
+    pub fn set_input_meter(&self, meter: Option<Arc<Mutex<LevelData>>>) {
+        if let Ok(mut input_meter) = self.input_meter.lock() {
+            *input_meter = meter;
+        }
+    }
+
+    /// Points `master-level` at the bus of the current main device.
+    pub fn set_master_bus(&self, levels: Arc<Mutex<BusLevels>>) {
+        if let Ok(mut master_bus) = self.master_bus.lock() {
+            *master_bus = Some(levels);
+        }
+    }
+
+    pub fn start_monitoring(&self) {
+        let app_handle = self.app_handle.clone();
+        let active_meters = Arc::clone(&self.active_meters);
+        let input_meter = Arc::clone(&self.input_meter);
+        let ducking = Arc::clone(&self.ducking);
+        let master_bus = Arc::clone(&self.master_bus);
+        
+        std::thread::spawn(move || {
+            loop {
+                let mut has_any_active_sink = false;
+                let mut should_duck = false;
+
+                let meters_snapshot = {
+                    if let Ok(mut meters) = active_meters.lock() {
+                        meters.retain(|(meter, sink, _)| {
+                            if sink.empty() {
+                                false
+                            } else if sink.is_paused() {
+                                // Paused sources stop refreshing their levels but are still alive
+                                true
+                            } else {
+                                if let Ok(levels) = meter.lock() {
+                                    Instant::now().duration_since(levels.last_update) < Duration::from_millis(200)
+                                } else {
+                                    false
+                                }
+                            }
+                        });
+                        
+                        if meters.is_empty() {
+                            None
+                        } else {
+                            Some(meters.clone())
+                        }
+                    } else {
+                        None
+                    }
+                };
+
+                if let Some(meters) = meters_snapshot {
+                    for (_, sink, triggers_ducking) in meters.iter() {
+                        if !sink.is_paused() {
+                            should_duck |= *triggers_ducking;
+                        }
+                        has_any_active_sink = true;
+                    }
+                }
+
+                ducking.set_active(should_duck);
+
+                let input_levels = input_meter.lock().ok()
+                    .and_then(|m| m.as_ref().and_then(|l| l.lock().ok().map(|l| l.clone())));
+                if let Some(levels) = &input_levels {
+                    let _ = app_handle.emit("mic-level", MasterLevelEvent {
+                        peak: levels.peak * levels.volume,
+                        rms: levels.rms * levels.volume,
+                        gain_reduction_db: 0.0,
+                    });
+                }
+
+                // Post-limiter levels, ignored once the bus stopped refreshing (e.g. device gone)
+                let bus_levels = master_bus.lock().ok()
+                    .and_then(|b| b.as_ref().and_then(|l| l.lock().ok().map(|l| l.clone())))
+                    .filter(|l| l.last_update.elapsed() < Duration::from_millis(200))
+                    .unwrap_or_default();
+                let _ = app_handle.emit("master-level", MasterLevelEvent {
+                    peak: bus_levels.peak,
+                    rms: bus_levels.rms,
+                    gain_reduction_db: bus_levels.gain_reduction_db,
+                });
+
+                let busy = has_any_active_sink || input_levels.is_some();
+                std::thread::sleep(Duration::from_millis(if busy { 16 } else { 250 }));
+            }
+        });
+    }
+}
+
+#[derive(Clone, Serialize)]
+struct AudioProgress {
+    id: String,
+    instance_id: u32,
+    name: String,
+    position_ms: u64,
+    duration_ms: u64,
+    is_paused: bool,
+    loop_count: u32,
+    /// Playback speed of `position_ms`, so the UI can interpolate between events.
+    tempo: f32,
+}
+
+#[derive(Clone)]
+struct CachedSound {
+    duration: Duration,
+    loudness: Option<LoudnessInfo>,
+    is_opus: bool,
+}
+
+/// Decoded PCM of a whole clip, resampled to the output device's rate.
+pub struct DecodedClip {
+    samples: Vec<f32>,
+    channels: u16,
+    sample_rate: u32,
+}
+
+impl DecodedClip {
+    fn duration(&self) -> Duration {
+        let frames = self.samples.len() / self.channels.max(1) as usize;
+        Duration::from_secs_f64(frames as f64 / self.sample_rate.max(1) as f64)
+    }
+
+    fn size_bytes(&self) -> usize {
+        self.samples.len() * std::mem::size_of::<f32>()
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SampleCacheSettings {
+    pub enabled: bool,
+    pub budget_mb: u32,
+    /// Longer clips keep streaming from disk.
+    pub max_clip_secs: f32,
+}
+
+impl Default for SampleCacheSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            budget_mb: 256,
+            max_clip_secs: 30.0,
+        }
+    }
+}
+
+/// Opt-in RAM cache of decoded clips, filled by `preload_sound`. Evicts the
+/// least recently played clips once the memory budget is exceeded.
+#[derive(Default)]
+pub struct SampleCache {
+    settings: SampleCacheSettings,
+    /// Clip and the tick it was last used at.
+    clips: HashMap<String, (Arc<DecodedClip>, u64)>,
+    bytes: usize,
+    clock: u64,
+}
+
+impl SampleCache {
+    fn budget_bytes(&self) -> usize {
+        self.settings.budget_mb as usize * 1024 * 1024
+    }
+
+    fn get(&mut self, path: &str) -> Option<Arc<DecodedClip>> {
+        self.clock += 1;
+        let (clip, last_used) = self.clips.get_mut(path)?;
+        *last_used = self.clock;
+        Some(Arc::clone(clip))
+    }
+
+    fn wants(&self, path: &str, sample_rate: u32) -> bool {
+        self.settings.enabled
+            && self.clips.get(path).is_none_or(|(clip, _)| clip.sample_rate != sample_rate)
+    }
+
+    fn insert(&mut self, path: String, clip: DecodedClip) {
+        if !self.settings.enabled || clip.size_bytes() > self.budget_bytes() {
+            return;
+        }
+        self.remove(&path);
+        self.clock += 1;
+        self.bytes += clip.size_bytes();
+        self.clips.insert(path, (Arc::new(clip), self.clock));
+        self.evict();
+    }
+
+    fn remove(&mut self, path: &str) {
+        if let Some((clip, _)) = self.clips.remove(path) {
+            self.bytes -= clip.size_bytes();
+        }
+    }
+
+    fn evict(&mut self) {
+        while self.bytes > self.budget_bytes() {
+            let oldest = self.clips.iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(path, _)| path.clone());
+            match oldest {
+                Some(path) => self.remove(&path),
+                None => break,
+            }
+        }
+    }
+
+    fn configure(&mut self, settings: SampleCacheSettings) {
+        self.settings = settings;
+        if !settings.enabled {
+            self.clips.clear();
+            self.bytes = 0;
+        }
+        self.evict();
+    }
+}
+
+/// Decodes a clip for the sample cache, or `None` if it is longer than `max_secs`.
+fn decode_clip(path: &str, is_opus: bool, sample_rate: u32, max_secs: f32) -> Option<DecodedClip> {
+    let source = open_source(path, None, is_opus).ok()?;
+    let channels = source.channels();
+    if source.total_duration().is_some_and(|d| d.as_secs_f32() > max_secs) {
+        return None;
+    }
+
+    // Also bounds files whose duration is unknown up front
+    let max_samples = (max_secs as f64 * sample_rate as f64) as usize * channels as usize;
+    let samples: Vec<f32> = UniformSourceIterator::new(source, channels, sample_rate)
+        .take(max_samples + 1)
+        .collect();
+    if samples.len() > max_samples {
+        return None;
+    }
+
+    Some(DecodedClip { samples, channels, sample_rate })
+}
+
+/// Opens `path` from the sample cache when possible, from disk otherwise.
+fn open_playback_source(
+    samples: &Mutex<SampleCache>,
+    path: &str,
+    start_time: Option<Duration>,
+    is_opus: bool,
+) -> Result<DecodedSource, String> {
+    let cached = samples.lock().ok().and_then(|mut cache| cache.get(path));
+    match cached {
+        Some(clip) => Ok(DecodedSource::Cached(CachedSource::new(clip, start_time))),
+        None => open_source(path, start_time, is_opus),
+    }
+}
+
+/// Container check, answered from the metadata cache when the file was seen before.
+fn cached_is_opus(cache: &Mutex<HashMap<String, CachedSound>>, path: &str) -> bool {
+    let known = cache.lock().ok().and_then(|c| c.get(path).map(|s| s.is_opus));
+    known.unwrap_or_else(|| is_opus_ogg(path))
+}
+
+#[derive(Clone, Serialize)]
+struct LoudnessAnalyzed {
+    path: String,
+    loudness: LoudnessInfo,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct NormalizationSettings {
+    pub target_lufs: f64,
+    /// Normalization never raises a clip's true peak above this.
+    pub ceiling_dbtp: f64,
+}
+
+impl Default for NormalizationSettings {
+    fn default() -> Self {
+        Self {
+            target_lufs: -16.0,
+            ceiling_dbtp: -1.0,
         }
     }
+}
+
+/// Decodes a whole file and measures its loudness.
+fn analyze_file(path: &str, is_opus: bool) -> Option<LoudnessInfo> {
+    let source = open_source(path, None, is_opus).ok()?;
+    let channels = source.channels();
+    let sample_rate = source.sample_rate();
+    loudness::analyze(source, channels, sample_rate)
+}
+
+fn open_source(path: &str, start_time: Option<Duration>, is_opus: bool) -> Result<DecodedSource, String> {
+    if is_opus {
+        OpusOggSource::new(path, start_time).map(DecodedSource::Opus)
+    } else {
+        SymphoniaSource::new(path, start_time).map(DecodedSource::Symphonia)
+    }
+}
 
-    pub fn add_meter(&self, meter: Arc<Mutex<LevelData>>, sink: Arc<Sink>) {
-        if let Ok(mut meters) = self.active_meters.lock() {
-            meters.push((meter, sink));
+/// What pressing a button does while it already has an instance playing.
+#[derive(Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerPolicy {
+    /// Start another overlapping instance.
+    #[default]
+    Polyphonic,
+    /// Stop the running instances and start from the beginning.
+    Restart,
+    /// Stop the running instances without starting a new one.
+    ToggleStop,
+    /// Do nothing until the running instances have finished.
+    IgnoreWhilePlaying,
+    /// Start once the running instances have finished.
+    Queue,
+}
+
+/// A button's saved trigger settings.
+struct ButtonTrigger {
+    policy: TriggerPolicy,
+    choke_group: Option<String>,
+}
+
+/// An instance that is starting or queued, before it has a sink.
+struct PendingInstance {
+    /// Button id.
+    id: String,
+    choke_group: Option<String>,
+}
+
+/// Per-play processing wrapped around a decoded source.
+type InstanceChain<S> = Ducker<Varispeed<Envelope<Effects<TrimEnd<Looper<S>>>>>>;
+
+/// Per-play options sent with `play_sound`; they survive seeks and device migrations.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PlaybackOptions {
+    pub duckable: bool,
+    pub start_ms: u64,
+    pub end_ms: Option<u64>,
+    pub fade_in_ms: u64,
+    pub fade_out_ms: u64,
+    pub looping: bool,
+    pub loop_start_ms: Option<u64>,
+    pub loop_end_ms: Option<u64>,
+    /// Defaults to the button's `set_button_trigger` policy.
+    pub trigger: Option<TriggerPolicy>,
+    /// Apply the gain that brings the clip to the normalization target.
+    pub normalize: bool,
+    /// Starting an instance stops every other instance in the same group.
+    /// Defaults to the button's `set_button_trigger` group.
+    pub choke_group: Option<String>,
+    /// Initial effect chain; `update_button_effects` changes it live.
+    pub effects: EffectsSettings,
+    /// Initial pitch and tempo; `update_instance_rate` changes them live.
+    pub pitch_semitones: f32,
+    pub tempo: Option<f32>,
+    /// Device id that plays this instance instead of the main and monitor outputs.
+    /// Defaults to the button's `set_button_device` override.
+    pub device: Option<String>,
+}
+
+impl PlaybackOptions {
+    fn tempo(&self) -> f32 {
+        self.tempo.unwrap_or(1.0)
+    }
+
+    fn trim_start(&self) -> Duration {
+        Duration::from_millis(self.start_ms)
+    }
+
+    fn trim_end(&self) -> Option<Duration> {
+        self.end_ms.map(Duration::from_millis)
+    }
+
+    /// Length of the playable region, given the full file duration.
+    fn region_duration(&self, file_duration: Duration) -> Duration {
+        let end = match self.trim_end() {
+            Some(end) if file_duration.is_zero() => end,
+            Some(end) => end.min(file_duration),
+            None => file_duration,
+        };
+        end.saturating_sub(self.trim_start())
+    }
+
+    /// Loop region in absolute file time, end `None` meaning the end of the file.
+    fn loop_bounds(&self) -> Option<(Duration, Option<Duration>)> {
+        if !self.looping {
+            return None;
         }
+        let start = self.loop_start_ms.map(Duration::from_millis).unwrap_or(self.trim_start());
+        let end = self.loop_end_ms.map(Duration::from_millis).or(self.trim_end());
+        Some((start, end))
     }
 
-    pub fn start_monitoring(&self) {
-        let app_handle = self.app_handle.clone();
-        let active_meters = Arc::clone(&self.active_meters);
-        let master_vol_ref = Arc::clone(&self.master_volume);
-        
-        std::thread::spawn(move || {
-            loop {
-                let mut master_peak = 0.0f32;
-                let mut master_rms = 0.0f32;
-                let mut has_any_active_sink = false;
+    /// Wraps a source opened at the absolute file `position` with the per-play processing.
+    fn wrap<S>(
+        &self,
+        source: S,
+        position: Duration,
+        file_duration: Duration,
+        ducking: &Arc<DuckControl>,
+        controls: &Arc<InstanceControls>,
+    ) -> InstanceChain<S>
+    where
+        S: Source<Item = f32> + Rewind,
+    {
+        // A looping instance never reaches the end of its region
+        let looped = Looper::new(source, Arc::clone(controls), position, self.loop_bounds());
+        let remaining = self.trim_end()
+            .filter(|_| !self.looping)
+            .map(|end| end.saturating_sub(position));
+        let region_length = Some(self.region_duration(file_duration))
+            .filter(|d| !d.is_zero() && !self.looping);
+        let envelope = Envelope::new(
+            Effects::new(TrimEnd::new(looped, remaining), Arc::clone(controls)),
+            Arc::clone(controls),
+            position.saturating_sub(self.trim_start()),
+            region_length,
+            Duration::from_millis(self.fade_in_ms),
+            Duration::from_millis(self.fade_out_ms),
+        );
+        let varispeed = Varispeed::new(envelope, Arc::clone(controls));
+        Ducker::new(varispeed, Arc::clone(ducking), DuckTarget::Instance { duckable: self.duckable })
+    }
+}
 
-                let meters_snapshot = {
-                    if let Ok(mut meters) = active_meters.lock() {
-                        meters.retain(|(meter, sink)| {
-                            if sink.empty() {
-                                false
-                            } else {
-                                if let Ok(levels) = meter.lock() {
-                                    Instant::now().duration_since(levels.last_update) < Duration::from_millis(200)
-                                } else {
-                                    false
-                                }
-                            }
-                        });
-                        
-                        if meters.is_empty() {
-                            None
-                        } else {
-                            Some(meters.iter().map(|(m, s)| (m.clone(), s.clone())).collect::<Vec<_>>())
-                        }
-                    } else {
-                        None
-                    }
-                };
+/// A playing sound: the sink on the main output plus an optional mirror
+/// on the monitor output, kept in lockstep.
+pub struct ActiveInstance {
+    pub id: String,
+    pub path: String,
+    pub is_opus: bool,
+    pub sink: Arc<Sink>,
+    pub monitor_sink: Option<Arc<Sink>>,
+    pub volume: f32,
+    pub options: PlaybackOptions,
+    pub file_duration: Duration,
+    pub controls: Arc<InstanceControls>,
+    /// Bus the main sink plays into.
+    pub bus: MasterBus,
+    /// Decoder position of the main output's source.
+    pub playhead: Arc<Playhead>,
+    pub levels: Arc<Mutex<LevelData>>,
+}
 
-                if let Some(meters) = meters_snapshot {
-                    let global_vol = *master_vol_ref.lock().unwrap();
-                    for (meter, sink) in meters.iter() {
-                        if let Ok(levels) = meter.lock() {
-                            if !sink.is_paused() {
-                                let vol = levels.volume * global_vol;
-                                master_peak = master_peak.max(levels.peak * vol);
-                                master_rms = master_rms.max(levels.rms * vol);
-                            }
-                            has_any_active_sink = true;
-                        }
-                    }
-                }
+impl ActiveInstance {
+    fn all_sinks(&self) -> impl Iterator<Item = &Arc<Sink>> {
+        std::iter::once(&self.sink).chain(self.monitor_sink.iter())
+    }
 
-                if has_any_active_sink {
-                    let _ = app_handle.emit("master-level", MasterLevelEvent {
-                        peak: master_peak,
-                        rms: master_rms,
-                    });
-                    std::thread::sleep(Duration::from_millis(16));
-                } else {
-                    let _ = app_handle.emit("master-level", MasterLevelEvent {
-                        peak: 0.0,
-                        rms: 0.0,
-                    });
-                    std::thread::sleep(Duration::from_millis(250));
-                }
-            }
-        });
+    pub fn is_paused(&self) -> bool {
+        self.sink.is_paused()
+    }
+
+    pub fn pause(&self) {
+        self.all_sinks().for_each(|s| s.pause());
+    }
+
+    pub fn play(&self) {
+        self.all_sinks().for_each(|s| s.play());
+    }
+
+    /// Current position in the file. Loops rewind the decoder, so this stays inside the loop region.
+    pub fn position(&self) -> Duration {
+        self.playhead.position()
+    }
+
+    pub fn stop(&self) {
+        self.all_sinks().for_each(|s| s.stop());
+    }
+
+    /// Fades out over `fade_ms` and lets the sources end on their own.
+    /// Returns `false` when the instance had to be stopped immediately instead.
+    pub fn stop_with_fade(&self, fade_ms: u64) -> bool {
+        // A paused sink is not pulled, so its envelope would never advance
+        if fade_ms == 0 || self.is_paused() {
+            self.stop();
+            return false;
+        }
+        self.controls.request_stop_fade(fade_ms);
+        true
     }
+
+    pub fn apply_volume(&self, master_vol: f32, monitor_vol: f32) {
+        self.sink.set_volume(self.volume * master_vol);
+        if let Some(monitor) = &self.monitor_sink {
+            monitor.set_volume(self.volume * monitor_vol);
+        }
+    }
+}
+
+type StreamEntry = (SendWrapper<cpal::Stream>, MasterBus);
+
+/// A bus whose levels stopped refreshing this long ago has lost its device.
+const DEVICE_STALE_AFTER: Duration = Duration::from_secs(2);
+const DEVICE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceRole {
+    Output,
+    Monitor,
+    /// A button override; its instances play on the main output meanwhile.
+    Button,
 }
 
 #[derive(Clone, Serialize)]
-struct AudioProgress {
-    id: String,
-    instance_id: u32,
-    name: String,
-    position_ms: u64,
-    duration_ms: u64,
-    is_paused: bool,
+pub struct DeviceEvent {
+    pub device_id: String,
+    pub device_name: String,
+    pub role: DeviceRole,
 }
 
-#[derive(Clone)]
-struct CachedSound {
-    duration: Duration,
+impl DeviceEvent {
+    fn new(device_id: String, role: DeviceRole) -> Self {
+        let device_name = devices::display_name(&device_id);
+        Self { device_id, device_name, role }
+    }
 }
 
 pub struct AudioState {
-    pub current_device_name: Arc<Mutex<String>>,
+    /// Selected outputs, as [`DeviceInfo`] ids.
+    pub current_device_id: Arc<Mutex<String>>,
+    pub monitor_device_id: Arc<Mutex<Option<String>>>,
     pub master_volume: Arc<Mutex<f32>>,
-    pub sinks: Arc<Mutex<HashMap<u32, (String, String, Arc<Sink>, f32, String, std::time::Instant, Duration, Arc<Mutex<LevelData>>)>>>,
-    active_streams: Arc<Mutex<HashMap<String, (SendWrapper<OutputStream>, OutputStreamHandle)>>>,
+    pub monitor_volume: Arc<Mutex<f32>>,
+    pub mic: Arc<Mutex<Option<MicPassthrough>>>,
+    pub mic_gain: Arc<Mutex<f32>>,
+    pub mic_muted: Arc<Mutex<bool>>,
+    pub ducking: Arc<DuckControl>,
+    pub sinks: Arc<Mutex<HashMap<u32, ActiveInstance>>>,
+    /// Instances that are starting or queued.
+    pending: Arc<Mutex<HashMap<u32, PendingInstance>>>,
+    active_streams: Arc<Mutex<HashMap<String, StreamEntry>>>,
     instance_counter: Arc<Mutex<u32>>,
     cache: Arc<Mutex<HashMap<String, CachedSound>>>,
+    samples: Arc<Mutex<SampleCache>>,
+    loudness_jobs: Arc<Mutex<std::collections::HashSet<String>>>,
+    pub normalization: Arc<Mutex<NormalizationSettings>>,
+    pub limiter: Arc<Mutex<LimiterSettings>>,
+    /// Audio backend every device is opened through.
+    host: Arc<Mutex<cpal::HostId>>,
+    output_configs: Arc<Mutex<HashMap<String, OutputConfig>>>,
+    /// Button id to the device id its instances play on instead of the main output.
+    button_devices: Arc<Mutex<HashMap<String, String>>>,
+    /// Button id to its trigger policy and choke group, applied on every trigger path.
+    button_triggers: Arc<Mutex<HashMap<String, ButtonTrigger>>>,
+    /// Selected devices that disappeared; their role falls back until they return.
+    lost_devices: Arc<Mutex<std::collections::HashSet<String>>>,
+    /// Hold shortcut presses that have not reached `play_sound` yet, with whether the
+    /// key was already released.
+    hold_presses: Arc<Mutex<HashMap<String, bool>>>,
     pub meter_manager: Arc<MeterManager>,
 }
 
-impl AudioState {
-    pub fn new(app_handle: AppHandle) -> Self {
-        let master_volume = Arc::new(Mutex::new(1.0));
-        let meter_manager = Arc::new(MeterManager::new(app_handle, Arc::clone(&master_volume)));
-        meter_manager.start_monitoring();
+impl AudioState {
+    pub fn new(app_handle: AppHandle) -> Self {
+        let master_volume = Arc::new(Mutex::new(1.0));
+        let ducking = Arc::new(DuckControl::new());
+        let meter_manager = Arc::new(MeterManager::new(app_handle, Arc::clone(&ducking)));
+        meter_manager.start_monitoring();
+
+        Self {
+            current_device_id: Arc::new(Mutex::new(DEFAULT_DEVICE.to_string())),
+            monitor_device_id: Arc::new(Mutex::new(None)),
+            master_volume,
+            monitor_volume: Arc::new(Mutex::new(1.0)),
+            mic: Arc::new(Mutex::new(None)),
+            mic_gain: Arc::new(Mutex::new(1.0)),
+            mic_muted: Arc::new(Mutex::new(false)),
+            ducking,
+            sinks: Arc::new(Mutex::new(HashMap::new())),
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            active_streams: Arc::new(Mutex::new(HashMap::new())),
+            instance_counter: Arc::new(Mutex::new(0)),
+            cache: Arc::new(Mutex::new(HashMap::new())),
+            samples: Arc::new(Mutex::new(SampleCache::default())),
+            loudness_jobs: Arc::new(Mutex::new(std::collections::HashSet::new())),
+            normalization: Arc::new(Mutex::new(NormalizationSettings::default())),
+            limiter: Arc::new(Mutex::new(LimiterSettings::default())),
+            host: Arc::new(Mutex::new(cpal::default_host().id())),
+            output_configs: Arc::new(Mutex::new(HashMap::new())),
+            button_devices: Arc::new(Mutex::new(HashMap::new())),
+            button_triggers: Arc::new(Mutex::new(HashMap::new())),
+            lost_devices: Arc::new(Mutex::new(std::collections::HashSet::new())),
+            hold_presses: Arc::new(Mutex::new(HashMap::new())),
+            meter_manager,
+        }
+    }
+
+    pub fn get_or_create_bus(&self, device_id: &str) -> Result<MasterBus, String> {
+        let mut streams = self.active_streams.lock().map_err(|_| "Failed to lock active streams")?;
+
+        if let Some((_, bus)) = streams.get(device_id) {
+            return Ok(bus.clone());
+        }
+
+        let device = devices::find_output_device(&self.host(), device_id).ok_or("Audio device not found")?;
+
+        // The bus mixes at the device's own format so rodio never has to convert it again
+        let requested = self.output_configs.lock().map_err(|_| "Failed to lock output configs")?
+            .get(device_id).copied().unwrap_or_default();
+        let (stream, bus) = MasterBus::start(&device, requested, Arc::clone(&self.limiter))?;
+        streams.insert(device_id.to_string(), (SendWrapper(stream), bus.clone()));
+
+        Ok(bus)
+    }
+
+    pub fn host(&self) -> cpal::Host {
+        let id = *self.host.lock().unwrap();
+        cpal::host_from_id(id).unwrap_or_else(|_| cpal::default_host())
+    }
+
+    /// Reopens the main and monitor outputs and moves every instance onto them.
+    fn reroute(&self) -> Result<(), String> {
+        let bus = self.get_main_bus()?;
+        let monitor_bus = self.get_monitor_bus()?;
+        self.migrate_active_sinks(&bus, monitor_bus.as_ref());
+        self.cleanup_streams();
+        Ok(())
+    }
+
+    fn is_lost(&self, device_id: &str) -> bool {
+        self.lost_devices.lock().is_ok_and(|lost| lost.contains(device_id))
+    }
+
+    /// Device the main output plays on: the selected one, or Default while it is lost.
+    fn output_device(&self) -> Result<String, String> {
+        let current = self.current_device_id.lock().map_err(|_| "Failed to lock device name")?.clone();
+        Ok(if self.is_lost(&current) { DEFAULT_DEVICE.to_string() } else { current })
+    }
+
+    /// Selected monitor device, or `None` while it is lost.
+    fn monitor_device(&self) -> Result<Option<String>, String> {
+        let monitor = self.monitor_device_id.lock().map_err(|_| "Failed to lock monitor device")?.clone();
+        Ok(monitor.filter(|name| !self.is_lost(name)))
+    }
+
+    /// Bus of the main output; also makes it the source of `master-level`.
+    pub fn get_main_bus(&self) -> Result<MasterBus, String> {
+        let current = self.output_device()?;
+        let bus = self.get_or_create_bus(&current)?;
+        self.meter_manager.set_master_bus(Arc::clone(&bus.levels));
+        Ok(bus)
+    }
+
+    /// Returns the monitor output bus, or `None` when no monitor is selected
+    /// or it points at the main device (which would play everything twice).
+    pub fn get_monitor_bus(&self) -> Result<Option<MasterBus>, String> {
+        let monitor = self.monitor_device()?;
+        let current = self.output_device()?;
+
+        match monitor {
+            Some(name) if name != current => self.get_or_create_bus(&name).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Bus of a device targeted by a button override, reopened if its stream died.
+    fn get_device_bus(&self, device_id: &str) -> Result<MasterBus, String> {
+        {
+            let mut streams = self.active_streams.lock().map_err(|_| "Failed to lock active streams")?;
+            if streams.get(device_id).is_some_and(|(_, bus)| bus.is_stale()) {
+                streams.remove(device_id);
+            }
+        }
+        self.get_or_create_bus(device_id)
+    }
+
+    /// Main and monitor bus for an instance; an override device replaces both unless it is lost.
+    fn instance_buses(&self, device: Option<&str>) -> Result<(MasterBus, Option<MasterBus>), String> {
+        match device {
+            Some(device_id) if !self.is_lost(device_id) => Ok((self.get_device_bus(device_id)?, None)),
+            _ => Ok((self.get_main_bus()?, self.get_monitor_bus()?)),
+        }
+    }
+
+    /// Closes streams that no output, running instance or button override uses.
+    pub fn cleanup_streams(&self) {
+        let mut keep: std::collections::HashSet<String> = self.output_device().into_iter()
+            .chain(self.monitor_device().ok().flatten())
+            .collect();
+        keep.extend(self.sinks.lock().unwrap().values().filter_map(|i| i.options.device.clone()));
+        keep.extend(self.button_devices.lock().unwrap().values().cloned());
+
+        let mut streams = self.active_streams.lock().unwrap();
+        streams.retain(|id, _| keep.contains(id));
+    }
+
+    pub fn migrate_active_sinks(&self, bus: &MasterBus, monitor_bus: Option<&MasterBus>) {
+        let mut sinks_guard = self.sinks.lock().unwrap();
+        let master_vol = *self.master_volume.lock().unwrap();
+        let monitor_vol = *self.monitor_volume.lock().unwrap();
+
+        for instance in sinks_guard.values_mut() {
+            let (target, target_monitor) = match &instance.options.device {
+                // Overridden instances only move when their own device was reopened, or
+                // to the main output when it cannot be
+                Some(device_id) if !self.is_lost(device_id) => match self.get_device_bus(device_id) {
+                    Ok(own) if own.same_as(&instance.bus) => continue,
+                    Ok(own) => (own, None),
+                    Err(_) => (bus.clone(), monitor_bus),
+                },
+                _ => (bus.clone(), monitor_bus),
+            };
+            let was_paused = instance.is_paused();
+            let current_pos = instance.position();
+
+            if let Ok(source) = open_playback_source(&self.samples, &instance.path, Some(current_pos), instance.is_opus) {
+                // Paused instances are rebuilt paused so their playhead does not move
+                let new_sink = Arc::new(target.new_sink());
+                if was_paused {
+                    new_sink.pause();
+                }
+                let playhead = source.playhead();
+                let new_levels = Arc::new(Mutex::new(LevelData {
+                    peak: 0.0,
+                    rms: 0.0,
+                    volume: instance.volume,
+                    last_update: Instant::now(),
+                }));
+                let processed = instance.options.wrap(source, current_pos, instance.file_duration, &self.ducking, &instance.controls);
+                let metered_source = LevelMeter::new(processed, new_levels.clone());
+
+                self.meter_manager.add_meter(new_levels.clone(), Arc::clone(&new_sink), !instance.options.duckable);
+                new_sink.append(metered_source);
+
+                let new_monitor = target_monitor.and_then(|b| {
+                    let sink = b.new_sink();
+                    if was_paused {
+                        sink.pause();
+                    }
+                    let source = open_playback_source(&self.samples, &instance.path, Some(current_pos), instance.is_opus).ok()?;
+                    sink.append(instance.options.wrap(source, current_pos, instance.file_duration, &self.ducking, &instance.controls));
+                    Some(Arc::new(sink))
+                });
+
+                instance.stop();
+                instance.sink = new_sink;
+                instance.monitor_sink = new_monitor;
+                instance.bus = target;
+                instance.apply_volume(master_vol, monitor_vol);
+                instance.playhead = playhead;
+                instance.levels = new_levels;
+            }
+        }
+
+        if let Some(mic) = self.mic.lock().unwrap().as_mut() {
+            let _ = mic.attach(bus);
+        }
+    }
+
+    /// Whether a selected device can be played on. Devices with a running stream are
+    /// judged by their bus, since enumeration may skip a device that is busy with it.
+    fn device_available(&self, device_id: &str) -> bool {
+        let bus = self.active_streams.lock().ok()
+            .and_then(|streams| streams.get(device_id).map(|(_, bus)| bus.clone()));
+        match bus {
+            Some(bus) => !bus.is_stale(),
+            None => devices::find_output_device(&self.host(), device_id).is_some(),
+        }
+    }
+
+    /// Moves playback off selected devices that vanished and back onto returning ones.
+    fn check_devices(&self, app: &AppHandle) {
+        if self.update_lost_devices(app) {
+            if let Err(e) = self.reroute() {
+                eprintln!("Failed to switch output after a device change: {}", e);
+            }
+        }
+    }
+
+    /// Marks selected devices lost or restored, emitting events. Returns whether any changed.
+    fn update_lost_devices(&self, app: &AppHandle) -> bool {
+        let current = self.current_device_id.lock().unwrap().clone();
+        let monitor = self.monitor_device_id.lock().unwrap().clone();
+        let mut selected = vec![(Some(current), DeviceRole::Output), (monitor, DeviceRole::Monitor)];
+        // Override devices of buttons and of running instances
+        let overrides: std::collections::BTreeSet<String> = self.button_devices.lock().unwrap().values().cloned()
+            .chain(self.sinks.lock().unwrap().values().filter_map(|i| i.options.device.clone()))
+            .collect();
+        selected.extend(overrides.into_iter().map(|id| (Some(id), DeviceRole::Button)));
+
+        let mut changed = false;
+        let mut seen = std::collections::HashSet::new();
+        for (id, role) in selected {
+            let Some(id) = id.filter(|id| id != DEFAULT_DEVICE) else { continue };
+            if !seen.insert(id.clone()) {
+                continue;
+            }
+            let was_lost = self.is_lost(&id);
+            if self.device_available(&id) != was_lost {
+                continue;
+            }
+
+            if was_lost {
+                // Only switch back once the device actually opens
+                if self.get_or_create_bus(&id).is_err() {
+                    continue;
+                }
+                self.lost_devices.lock().unwrap().remove(&id);
+                let _ = app.emit("device-restored", DeviceEvent::new(id, role));
+            } else {
+                // The dead stream cannot be reused once the device comes back
+                self.active_streams.lock().unwrap().remove(&id);
+                self.lost_devices.lock().unwrap().insert(id.clone());
+                let _ = app.emit("device-lost", DeviceEvent::new(id, role));
+            }
+            changed = true;
+        }
+        changed
+    }
+
+    /// Stops every instance of a button, fading out over `fade_ms`. Returns whether any was running.
+    pub fn stop_button(&self, button_id: &str, fade_ms: u64) -> bool {
+        let mut sinks = self.sinks.lock().unwrap();
+        let mut pending = self.pending.lock().unwrap();
+        let before = pending.len();
+        pending.retain(|_, instance| instance.id != button_id);
+        let mut found = pending.len() < before;
+        sinks.retain(|_, instance| {
+            if instance.id != button_id {
+                return true;
+            }
+            found = true;
+            instance.stop_with_fade(fade_ms)
+        });
+        found
+    }
+
+    /// Marks a hold shortcut press as on its way to `play_sound`.
+    pub fn press_button(&self, button_id: &str) {
+        self.hold_presses.lock().unwrap().insert(button_id.to_string(), false);
+    }
+
+    /// Stops a button when its hold shortcut is released. If the press has not reached
+    /// `play_sound` yet, the play it triggered is cancelled instead.
+    pub fn release_button(&self, button_id: &str, fade_ms: u64) {
+        if !self.stop_button(button_id, fade_ms) {
+            if let Some(released) = self.hold_presses.lock().unwrap().get_mut(button_id) {
+                *released = true;
+            }
+        }
+    }
+
+    /// Consumes the pending hold press of a button. Returns whether it was already released.
+    fn take_hold_release(&self, button_id: &str) -> bool {
+        self.hold_presses.lock().unwrap().remove(button_id).unwrap_or(false)
+    }
+}
+
+/// Polls the selected devices for as long as the app runs. Needs `AudioState` to be managed.
+pub fn start_device_watcher(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(DEVICE_POLL_INTERVAL);
+        app.state::<AudioState>().check_devices(&app);
+    });
+}
+
+#[derive(Clone, Serialize)]
+pub struct AudioHostInfo {
+    pub name: String,
+    pub is_default: bool,
+    pub is_selected: bool,
+}
+
+/// Backends compiled in and usable on this machine (e.g. ALSA and JACK on Linux, WASAPI and ASIO on Windows).
+#[tauri::command]
+pub async fn list_audio_hosts(state: State<'_, AudioState>) -> Result<Vec<AudioHostInfo>, String> {
+    let default = cpal::default_host().id();
+    let selected = *state.host.lock().map_err(|_| "Failed to lock audio host")?;
+    Ok(cpal::available_hosts()
+        .into_iter()
+        .map(|id| AudioHostInfo {
+            name: id.name().to_string(),
+            is_default: id == default,
+            is_selected: id == selected,
+        })
+        .collect())
+}
+
+/// Switches the audio backend. Outputs the new host does not have fall back like lost devices.
+#[tauri::command]
+pub async fn set_audio_host(app: AppHandle, state: State<'_, AudioState>, host: String) -> Result<(), String> {
+    let id = cpal::available_hosts()
+        .into_iter()
+        .find(|id| id.name().eq_ignore_ascii_case(&host))
+        .ok_or("Audio host not available")?;
+    cpal::host_from_id(id).map_err(|e| e.to_string())?;
+
+    {
+        let mut host_guard = state.host.lock().map_err(|_| "Failed to lock audio host")?;
+        if *host_guard == id {
+            return Ok(());
+        }
+        *host_guard = id;
+    }
+
+    // Streams of the previous host are dropped; instances are rebuilt on the new one
+    state.active_streams.lock().map_err(|_| "Failed to lock active streams")?.clear();
+    state.lost_devices.lock().map_err(|_| "Failed to lock lost devices")?.clear();
+    state.update_lost_devices(&app);
+    state.reroute()
+}
+
+/// Sets the sample rate and buffer size used for `device_id`, reopening it if it is playing.
+#[tauri::command]
+pub async fn set_output_config(state: State<'_, AudioState>, device_id: String, config: OutputConfig) -> Result<(), String> {
+    let previous = state.output_configs.lock().map_err(|_| "Failed to lock output configs")?
+        .insert(device_id.clone(), config);
+    if previous.unwrap_or_default() == config {
+        return Ok(());
+    }
+
+    let removed = state.active_streams.lock().map_err(|_| "Failed to lock active streams")?
+        .remove(&device_id);
+    if removed.is_some() {
+        state.reroute()?;
+    }
+    Ok(())
+}
+
+/// Output devices of the selected host, `Default` first.
+#[tauri::command]
+pub async fn list_audio_devices(state: State<'_, AudioState>) -> Result<Vec<DeviceInfo>, String> {
+    let host = state.host();
+    let mut infos: Vec<DeviceInfo> = devices::output_devices(&host)?
+        .into_iter()
+        .filter(|(_, device)| device.name().is_ok_and(|name| devices::is_listed_device(host.id(), &name)))
+        .map(|(id, device)| devices::describe(&host, id, &device))
+        .collect();
+    infos.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.id.cmp(&b.id)));
+
+    if let Some(device) = host.default_output_device() {
+        infos.insert(0, devices::describe(&host, DEFAULT_DEVICE.to_string(), &device));
+    }
+    Ok(infos)
+}
+
+/// Selects the main output. Returns the device actually used, which differs from `device_id`
+/// when a saved device was renamed and matched by name, or stands in for it as a suggestion.
+#[tauri::command]
+pub async fn set_audio_device(state: State<'_, AudioState>, device_id: String) -> Result<DeviceSelection, String> {
+    let selection = devices::resolve_output_id(&state.host(), &device_id)?;
+    let device_id = selection.id.clone();
+    let old_device = {
+        let mut device_id_guard = state
+            .current_device_id
+            .lock()
+            .map_err(|_| "Failed to lock audio state")?;
+        std::mem::replace(&mut *device_id_guard, device_id.clone())
+    };
+
+    if old_device != device_id {
+        // Pre-initialize stream for the new device
+        let bus = state.get_main_bus()?;
+        let monitor_bus = state.get_monitor_bus()?;
+
+        // Migrate all active sinks to the new device bus
+        state.migrate_active_sinks(&bus, monitor_bus.as_ref());
+
+        // Cleanup old device streams
+        state.cleanup_streams();
+    }
+
+    Ok(selection)
+}
+
+/// Selects the monitor output, resolved like [`set_audio_device`].
+#[tauri::command]
+pub async fn set_monitor_device(state: State<'_, AudioState>, device_id: Option<String>) -> Result<Option<DeviceSelection>, String> {
+    let selection = device_id.map(|id| devices::resolve_output_id(&state.host(), &id)).transpose()?;
+    let device_id = selection.as_ref().map(|s| s.id.clone());
+    let old_device = {
+        let mut monitor_guard = state
+            .monitor_device_id
+            .lock()
+            .map_err(|_| "Failed to lock audio state")?;
+        std::mem::replace(&mut *monitor_guard, device_id.clone())
+    };
 
-        Self {
-            current_device_name: Arc::new(Mutex::new("Default".to_string())),
-            master_volume,
-            sinks: Arc::new(Mutex::new(HashMap::new())),
-            active_streams: Arc::new(Mutex::new(HashMap::new())),
-            instance_counter: Arc::new(Mutex::new(0)),
-            cache: Arc::new(Mutex::new(HashMap::new())),
-            meter_manager,
-        }
+    if old_device != device_id {
+        let bus = state.get_main_bus()?;
+        let monitor_bus = state.get_monitor_bus()?;
+
+        // Rebuild both sinks so the main and monitor outputs stay aligned
+        state.migrate_active_sinks(&bus, monitor_bus.as_ref());
+        state.cleanup_streams();
     }
 
-    pub fn get_or_create_stream_handle(&self, device_name: &str) -> Result<OutputStreamHandle, String> {
-        let mut streams = self.active_streams.lock().map_err(|_| "Failed to lock active streams")?;
-        
-        if let Some((_, handle)) = streams.get(device_name) {
-            return Ok(handle.clone());
-        }
+    Ok(selection)
+}
 
-        let host = cpal::default_host();
-        let device = if device_name == "Default" {
-            host.default_output_device()
-        } else {
-            host.output_devices().map_err(|e| e.to_string())?
-                .find(|d| d.name().map(|n| n == device_name).unwrap_or(false))
-        }.ok_or("Audio device not found")?;
+/// Sends every instance of a button to `device_id` instead of the main and monitor outputs,
+/// or back to them with `None`. Returns the resolved device, like [`set_audio_device`].
+#[tauri::command]
+pub async fn set_button_device(state: State<'_, AudioState>, button_id: String, device_id: Option<String>) -> Result<Option<DeviceSelection>, String> {
+    let selection = device_id.map(|id| devices::resolve_output_id(&state.host(), &id)).transpose()?;
+    let device_id = selection.as_ref().map(|s| s.id.clone());
+    {
+        let mut button_devices = state.button_devices.lock().map_err(|_| "Failed to lock button devices")?;
+        match &device_id {
+            Some(id) => button_devices.insert(button_id, id.clone()),
+            None => button_devices.remove(&button_id),
+        };
+    }
 
-        let (stream, handle) = OutputStream::try_from_device(&device).map_err(|e| e.to_string())?;
-        streams.insert(device_name.to_string(), (SendWrapper(stream), handle.clone()));
-        
-        Ok(handle)
+    // Open the device now so the first trigger does not wait for it
+    match &device_id {
+        Some(id) => {
+            state.get_device_bus(id)?;
+        }
+        None => state.cleanup_streams(),
     }
+    Ok(selection)
+}
 
-    pub fn cleanup_streams(&self, except_device: &str) {
-        let mut streams = self.active_streams.lock().unwrap();
-        streams.retain(|name, _| name == except_device);
+/// Sets what retriggering a button does and the choke group it belongs to. Every trigger
+/// path (board, shortcuts, MIDI, OSC, control API) goes through `play_sound` and uses them.
+#[tauri::command]
+pub async fn set_button_trigger(
+    state: State<'_, AudioState>,
+    button_id: String,
+    trigger: TriggerPolicy,
+    choke_group: Option<String>,
+) -> Result<(), String> {
+    let mut button_triggers = state.button_triggers.lock().map_err(|_| "Failed to lock button triggers")?;
+    let choke_group = choke_group.filter(|group| !group.is_empty());
+    if trigger == TriggerPolicy::Polyphonic && choke_group.is_none() {
+        button_triggers.remove(&button_id);
+    } else {
+        button_triggers.insert(button_id, ButtonTrigger { policy: trigger, choke_group });
     }
+    Ok(())
+}
 
-    pub fn migrate_active_sinks(&self, handle: &OutputStreamHandle) {
-        let mut sinks_guard = self.sinks.lock().unwrap();
-        let master_vol = *self.master_volume.lock().unwrap();
+#[tauri::command]
+pub async fn update_master_volume(state: State<'_, AudioState>, volume: f32) -> Result<(), String> {
+    let mut master_vol = state.master_volume.lock().unwrap();
+    *master_vol = volume;
+    let monitor_vol = *state.monitor_volume.lock().unwrap();
 
-        for (_instance_id, (_id, path, sink, volume, _name, start_time, base_offset, levels)) in sinks_guard.iter_mut() {
-            let elapsed = if sink.is_paused() {
-                Duration::from_secs(0)
-            } else {
-                start_time.elapsed()
-            };
-            let current_pos = elapsed + *base_offset;
-
-            if let Ok(new_sink) = Sink::try_new(handle) {
-                let new_sink = Arc::new(new_sink);
-                
-                if let Ok(source) = SymphoniaSource::new(path, Some(current_pos)) {
-                    let new_levels = Arc::new(Mutex::new(LevelData {
-                        peak: 0.0,
-                        rms: 0.0,
-                        volume: *volume,
-                        last_update: Instant::now(),
-                    }));
-                    let metered_source = LevelMeter::new(source, new_levels.clone());
-                    
-                    self.meter_manager.add_meter(new_levels.clone(), Arc::clone(&new_sink));
-                    
-                    new_sink.set_volume(*volume * master_vol);
-                    new_sink.append(metered_source);
-                    
-                    if sink.is_paused() {
-                        new_sink.pause();
-                    }
+    let sinks = state.sinks.lock().unwrap();
+    for instance in sinks.values() {
+        instance.apply_volume(volume, monitor_vol);
+    }
+    Ok(())
+}
 
-                    sink.stop();
-                    *sink = new_sink;
-                    *start_time = std::time::Instant::now();
-                    *base_offset = current_pos;
-                    *levels = new_levels;
-                }
-            }
-        }
+#[tauri::command]
+pub async fn update_monitor_volume(state: State<'_, AudioState>, volume: f32) -> Result<(), String> {
+    let mut monitor_vol = state.monitor_volume.lock().unwrap();
+    *monitor_vol = volume;
+    let master_vol = *state.master_volume.lock().unwrap();
+
+    let sinks = state.sinks.lock().unwrap();
+    for instance in sinks.values() {
+        instance.apply_volume(master_vol, volume);
     }
+    Ok(())
 }
 
 #[tauri::command]
-pub async fn list_audio_devices() -> Result<Vec<String>, String> {
-    let host = cpal::default_host();
-    let devices = host.output_devices().map_err(|e| e.to_string())?;
+pub async fn list_input_devices(state: State<'_, AudioState>) -> Result<Vec<String>, String> {
+    let host = state.host();
+    let devices = host.input_devices().map_err(|e| e.to_string())?;
     let mut names: Vec<String> = devices.filter_map(|d| d.name().ok()).collect();
-    
-    names.retain(|name| {
-        let n = name.to_lowercase();
-        !n.starts_with("hw:") && 
-        !n.starts_with("plughw:") && 
-        !n.starts_with("dmix:") && 
-        !n.starts_with("dsnoop:") &&
-        !n.ends_with("rate") && 
-        !n.starts_with("speex") &&
-        !n.contains("surround") &&
-        !n.contains("upmix") &&
-        !n.contains("vdownmix")
-    });
-    
+
+    names.retain(|name| devices::is_listed_device(host.id(), name));
+
     names.sort();
     names.dedup();
-    
+
     names.insert(0, "Default".to_string());
     Ok(names)
 }
 
 #[tauri::command]
-pub async fn set_audio_device(state: State<'_, AudioState>, device_name: String) -> Result<(), String> {
-    let old_device = {
-        let mut device_name_guard = state
-            .current_device_name
-            .lock()
-            .map_err(|_| "Failed to lock audio state")?;
-        let old = device_name_guard.clone();
-        *device_name_guard = device_name.clone();
-        old
-    };
+pub async fn set_mic_input(state: State<'_, AudioState>, device_name: Option<String>) -> Result<(), String> {
+    let mut mic_guard = state.mic.lock().map_err(|_| "Failed to lock microphone")?;
 
-    if old_device != device_name {
-        // Pre-initialize stream for the new device
-        let handle = state.get_or_create_stream_handle(&device_name)?;
-        
-        // Migrate all active sinks to the new device handle
-        state.migrate_active_sinks(&handle);
+    if mic_guard.as_ref().map(|m| &m.device_name) == device_name.as_ref() {
+        return Ok(());
+    }
 
-        // Cleanup old device streams
-        state.cleanup_streams(&device_name);
+    // Drop the previous capture first so the device is released before reopening
+    *mic_guard = None;
+    state.meter_manager.set_input_meter(None);
+
+    if let Some(name) = device_name {
+        let bus = state.get_main_bus()?;
+        let mic = MicPassthrough::start(&state.host(), &name, &bus, Arc::clone(&state.ducking))?;
+
+        let gain = *state.mic_gain.lock().unwrap();
+        let muted = *state.mic_muted.lock().unwrap();
+        mic.apply_gain(gain, muted);
+        state.meter_manager.set_input_meter(Some(Arc::clone(&mic.levels)));
+        *mic_guard = Some(mic);
     }
 
     Ok(())
 }
 
 #[tauri::command]
-pub async fn update_master_volume(state: State<'_, AudioState>, volume: f32) -> Result<(), String> {
-    let mut master_vol = state.master_volume.lock().unwrap();
-    *master_vol = volume;
-    
-    let sinks = state.sinks.lock().unwrap();
-    for (_, (_, _, sink, button_vol, _, _, _, _)) in sinks.iter() {
-        sink.set_volume(button_vol * volume);
+pub async fn update_mic_gain(state: State<'_, AudioState>, gain: f32) -> Result<(), String> {
+    *state.mic_gain.lock().unwrap() = gain;
+    let muted = *state.mic_muted.lock().unwrap();
+
+    if let Some(mic) = state.mic.lock().unwrap().as_ref() {
+        mic.apply_gain(gain, muted);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn set_mic_muted(state: State<'_, AudioState>, muted: bool) -> Result<(), String> {
+    *state.mic_muted.lock().unwrap() = muted;
+    let gain = *state.mic_gain.lock().unwrap();
+
+    if let Some(mic) = state.mic.lock().unwrap().as_ref() {
+        mic.apply_gain(gain, muted);
     }
     Ok(())
 }
 
 #[tauri::command]
-pub async fn preload_sound(state: State<'_, AudioState>, path: String) -> Result<(), String> {
+pub async fn update_ducking(state: State<'_, AudioState>, settings: DuckingSettings) -> Result<(), String> {
+    state.ducking.update_settings(settings);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn preload_sound(app: AppHandle, state: State<'_, AudioState>, path: String) -> Result<(), String> {
     let cache = Arc::clone(&state.cache);
-    let is_opus = is_opus_ogg(&path);
-    
+    let samples = Arc::clone(&state.samples);
+    let loudness_jobs = Arc::clone(&state.loudness_jobs);
+    let is_opus = cached_is_opus(&cache, &path);
+    // Only the sample cache needs the output rate; a missing device must not block the analysis
+    let cache_enabled = samples.lock().map_err(|_| "Failed to lock sample cache")?.settings.enabled;
+    let sample_rate = cache_enabled.then(|| state.get_main_bus().ok()).flatten().map(|bus| bus.sample_rate);
+
     std::thread::spawn(move || {
-        let mut cache_guard = cache.lock().unwrap();
-        if !cache_guard.contains_key(&path) {
-            let duration = if is_opus {
-                OpusOggSource::new(&path, None)
-                    .ok()
-                    .and_then(|s| s.total_duration())
-            } else {
-                SymphoniaSource::new(&path, None)
+        let needs_analysis = {
+            let mut cache_guard = cache.lock().unwrap();
+            if let std::collections::hash_map::Entry::Vacant(entry) = cache_guard.entry(path.clone()) {
+                let duration = open_source(entry.key(), None, is_opus)
                     .ok()
-                    .and_then(|s| s.total_duration())
-            };
-            if let Some(d) = duration {
-                cache_guard.insert(path, CachedSound { duration: d });
+                    .and_then(|s| s.total_duration());
+                if let Some(d) = duration {
+                    entry.insert(CachedSound { duration: d, loudness: None, is_opus });
+                }
+            }
+            cache_guard.get(&path).is_some_and(|c| c.loudness.is_none())
+        };
+
+        let to_cache = sample_rate.and_then(|sample_rate| {
+            let samples_guard = samples.lock().unwrap();
+            samples_guard.wants(&path, sample_rate).then_some((sample_rate, samples_guard.settings.max_clip_secs))
+        });
+        if let Some((sample_rate, max_secs)) = to_cache {
+            if let Some(clip) = decode_clip(&path, is_opus, sample_rate, max_secs) {
+                samples.lock().unwrap().insert(path.clone(), clip);
+            }
+        }
+
+        // Analysis decodes the whole file, so it runs without holding the cache lock
+        if !needs_analysis || !loudness_jobs.lock().unwrap().insert(path.clone()) {
+            return;
+        }
+
+        let result = analyze_file(&path, is_opus);
+        if let Some(info) = result {
+            if let Some(cached) = cache.lock().unwrap().get_mut(&path) {
+                cached.loudness = Some(info);
             }
+            let _ = app.emit("loudness-analyzed", LoudnessAnalyzed {
+                path: path.clone(),
+                loudness: info,
+            });
         }
+        loudness_jobs.lock().unwrap().remove(&path);
     });
     Ok(())
 }
 
+#[tauri::command]
+pub async fn get_loudness(state: State<'_, AudioState>, path: String) -> Result<Option<LoudnessInfo>, String> {
+    let cache = state.cache.lock().map_err(|_| "Failed to lock cache")?;
+    Ok(cache.get(&path).and_then(|c| c.loudness))
+}
+
+#[tauri::command]
+pub async fn update_normalization(state: State<'_, AudioState>, settings: NormalizationSettings) -> Result<(), String> {
+    *state.normalization.lock().map_err(|_| "Failed to lock normalization settings")? = settings;
+    Ok(())
+}
+
+/// Updates the master bus limiter. Running buses pick the change up within a few milliseconds.
+#[tauri::command]
+pub async fn update_limiter(state: State<'_, AudioState>, settings: LimiterSettings) -> Result<(), String> {
+    *state.limiter.lock().map_err(|_| "Failed to lock limiter settings")? = settings;
+    Ok(())
+}
+
+fn validate_rate(pitch_semitones: f32, tempo: f32) -> Result<(), String> {
+    if !(-24.0..=24.0).contains(&pitch_semitones) {
+        return Err("Pitch must be between -24 and 24 semitones".to_string());
+    }
+    if !(0.25..=4.0).contains(&tempo) {
+        return Err("Tempo must be between 0.25 and 4".to_string());
+    }
+    Ok(())
+}
+
+/// Configures the decoded sample cache. Disabling it frees every cached clip.
+#[tauri::command]
+pub async fn update_sample_cache(state: State<'_, AudioState>, settings: SampleCacheSettings) -> Result<(), String> {
+    state.samples.lock().map_err(|_| "Failed to lock sample cache")?.configure(settings);
+    Ok(())
+}
+
+/// Stops and unregisters instances; their progress loops then report `audio-finished`.
+fn remove_instances(sinks: &mut HashMap<u32, ActiveInstance>, pending: &mut HashMap<u32, PendingInstance>, instance_ids: &[u32]) {
+    for instance_id in instance_ids {
+        if let Some(instance) = sinks.remove(instance_id) {
+            instance.stop();
+        }
+        pending.remove(instance_id);
+    }
+}
+
 #[tauri::command]
 pub async fn play_sound(
     app: AppHandle,
@@ -722,173 +3289,278 @@ pub async fn play_sound(
     path: String,
     name: String,
     volume: f32,
-) -> Result<u32, String> {
-    let device_name = state.current_device_name.lock().map_err(|_| "Failed to lock device name")?.clone();
+    options: Option<PlaybackOptions>,
+) -> Result<Option<u32>, String> {
+    let mut options = options.unwrap_or_default();
+    if options.device.is_none() {
+        options.device = state.button_devices.lock().map_err(|_| "Failed to lock button devices")?.get(&id).cloned();
+    }
+    if let Some(saved) = state.button_triggers.lock().map_err(|_| "Failed to lock button triggers")?.get(&id) {
+        options.trigger = options.trigger.or(Some(saved.policy));
+        options.choke_group = options.choke_group.take().or_else(|| saved.choke_group.clone());
+    }
+    // The key of a hold-to-play shortcut was let go before the sound started
+    if state.take_hold_release(&id) {
+        return Ok(None);
+    }
+    if options.end_ms.is_some_and(|end| end <= options.start_ms) {
+        return Err("Trim end must be after trim start".to_string());
+    }
+    if let Some((start, Some(end))) = options.loop_bounds() {
+        if end <= start {
+            return Err("Loop end must be after loop start".to_string());
+        }
+    }
+    validate_rate(options.pitch_semitones, options.tempo())?;
     let master_vol = *state.master_volume.lock().unwrap();
-    
-    // Get cached handle or create new one (eliminates initialization latency)
-    let stream_handle = state.get_or_create_stream_handle(&device_name)?;
+    let monitor_vol = *state.monitor_volume.lock().unwrap();
+
+    // Get cached bus or create new one (eliminates initialization latency)
+    let (bus, monitor_bus) = state.instance_buses(options.device.as_deref())?;
 
     let sinks = Arc::clone(&state.sinks);
+    let pending = Arc::clone(&state.pending);
     let cache = Arc::clone(&state.cache);
-    
+
     let mut counter = state.instance_counter.lock().unwrap();
-    *counter += 1;
+
+    // Apply the trigger policy and reserve the instance in one critical section,
+    // so rapid retriggers see instances that have not started yet
+    let wait_for = {
+        let mut sinks_guard = sinks.lock().map_err(|_| "Failed to lock sinks")?;
+        let mut pending_guard = pending.lock().map_err(|_| "Failed to lock pending instances")?;
+
+        let running: Vec<u32> = sinks_guard.iter()
+            .filter(|(_, instance)| instance.id == id)
+            .map(|(instance_id, _)| *instance_id)
+            .chain(pending_guard.iter().filter(|(_, instance)| instance.id == id).map(|(instance_id, _)| *instance_id))
+            .collect();
+
+        let wait_for = match options.trigger.unwrap_or_default() {
+            TriggerPolicy::Polyphonic => Vec::new(),
+            TriggerPolicy::Restart => {
+                remove_instances(&mut sinks_guard, &mut pending_guard, &running);
+                Vec::new()
+            }
+            TriggerPolicy::ToggleStop if !running.is_empty() => {
+                remove_instances(&mut sinks_guard, &mut pending_guard, &running);
+                return Ok(None);
+            }
+            TriggerPolicy::IgnoreWhilePlaying if !running.is_empty() => return Ok(None),
+            TriggerPolicy::Queue => running,
+            TriggerPolicy::ToggleStop | TriggerPolicy::IgnoreWhilePlaying => Vec::new(),
+        };
+
+        *counter += 1;
+        pending_guard.insert(*counter, PendingInstance { id: id.clone(), choke_group: options.choke_group.clone() });
+        wait_for
+    };
     let instance_id = *counter;
 
     let id_clone = id.clone();
     let name_clone = name.clone();
     let path_clone = path.clone();
     let meter_manager = Arc::clone(&state.meter_manager);
-    let is_opus = is_opus_ogg(&path_clone);
-    
+    let ducking = Arc::clone(&state.ducking);
+    let normalization = state.normalization.lock().map_err(|_| "Failed to lock normalization settings")?.clone();
+    let samples = Arc::clone(&state.samples);
+    let is_opus = cached_is_opus(&cache, &path_clone);
+
     std::thread::spawn(move || {
-        let duration = {
+        // Queued triggers wait for the instances that were playing when they were requested
+        while !wait_for.is_empty() {
+            let still_playing = {
+                let sinks_guard = sinks.lock().unwrap();
+                let pending_guard = pending.lock().unwrap();
+                if !pending_guard.contains_key(&instance_id) {
+                    // Cancelled while waiting
+                    let _ = app.emit("audio-finished", instance_id);
+                    return;
+                }
+                wait_for.iter().any(|w| sinks_guard.contains_key(w) || pending_guard.contains_key(w))
+            };
+            if !still_playing {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        let (duration, loudness) = {
             let mut cache_guard = cache.lock().unwrap();
             if let Some(cached) = cache_guard.get(&path_clone) {
-                cached.duration
+                (cached.duration, cached.loudness)
             } else {
-                let d = if is_opus {
-                    OpusOggSource::new(&path_clone, None)
-                        .ok()
-                        .and_then(|s| s.total_duration())
-                        .unwrap_or(Duration::from_secs(0))
-                } else {
-                    SymphoniaSource::new(&path_clone, None)
-                        .ok()
-                        .and_then(|s| s.total_duration())
-                        .unwrap_or(Duration::from_secs(0))
-                };
-                cache_guard.insert(path_clone.clone(), CachedSound { duration: d });
-                d
+                let d = open_source(&path_clone, None, is_opus)
+                    .ok()
+                    .and_then(|s| s.total_duration())
+                    .unwrap_or(Duration::from_secs(0));
+                cache_guard.insert(path_clone.clone(), CachedSound { duration: d, loudness: None, is_opus });
+                (d, None)
             }
         };
 
-        let result = if is_opus {
-            OpusOggSource::new(&path_clone, None).map(|s| Box::new(s) as Box<dyn Source<Item = f32> + Send>)
-        } else {
-            SymphoniaSource::new(&path_clone, None).map(|s| Box::new(s) as Box<dyn Source<Item = f32> + Send>)
-        };
+        // Until the file has been analyzed it plays at its natural level
+        let normalization_gain = loudness
+            .filter(|_| options.normalize)
+            .and_then(|info| info.normalization_gain_db(normalization.target_lufs, normalization.ceiling_dbtp))
+            .map_or(1.0, |gain_db| 10f32.powf(gain_db as f32 / 20.0));
+
+        let start_position = options.trim_start();
+        let start_time_arg = Some(start_position).filter(|p| !p.is_zero());
+        let result = open_playback_source(&samples, &path_clone, start_time_arg, is_opus);
 
         if let Ok(source) = result {
-            if let Ok(sink) = Sink::try_new(&stream_handle) {
-                let sink = Arc::new(sink);
-                
-                let levels = Arc::new(Mutex::new(LevelData {
-                    peak: 0.0,
-                    rms: 0.0,
-                    volume,
-                    last_update: Instant::now(),
-                }));
-                
-                let metered_source = LevelMeter::new(source, levels.clone());
-                sink.append(metered_source);
-                
-                meter_manager.add_meter(levels.clone(), Arc::clone(&sink));
-                sink.set_volume(volume * master_vol);
-                
-                let start_time = std::time::Instant::now();
-                let base_offset = Duration::from_secs(0);
-
-                {
-                    let mut sinks_guard = sinks.lock().unwrap();
-                    sinks_guard.insert(instance_id, (id_clone.clone(), path_clone.clone(), Arc::clone(&sink), volume, name_clone.clone(), start_time, base_offset, levels.clone()));
+            let sink = Arc::new(bus.new_sink());
+            let playhead = source.playhead();
+
+            let levels = Arc::new(Mutex::new(LevelData {
+                peak: 0.0,
+                rms: 0.0,
+                volume,
+                last_update: Instant::now(),
+            }));
+
+            let controls = Arc::new(InstanceControls::new(
+                normalization_gain,
+                options.effects.clone(),
+                options.pitch_semitones,
+                options.tempo(),
+            ));
+
+            // The monitor gets its own decoder so both outputs pull independently
+            let monitor_sink = monitor_bus.as_ref().and_then(|b| {
+                let sink = b.new_sink();
+                let source = open_playback_source(&samples, &path_clone, start_time_arg, is_opus).ok()?;
+                sink.set_volume(volume * monitor_vol);
+                sink.append(options.wrap(source, start_position, duration, &ducking, &controls));
+                Some(Arc::new(sink))
+            });
+
+            let processed = options.wrap(source, start_position, duration, &ducking, &controls);
+            let metered_source = LevelMeter::new(processed, levels.clone());
+            sink.append(metered_source);
+
+            meter_manager.add_meter(levels.clone(), Arc::clone(&sink), !options.duckable);
+            sink.set_volume(volume * master_vol);
+
+            {
+                let mut sinks_guard = sinks.lock().unwrap();
+                let mut pending_guard = pending.lock().unwrap();
+
+                if pending_guard.remove(&instance_id).is_none() {
+                    // Stopped before it finished starting
+                    sink.stop();
+                    if let Some(monitor) = &monitor_sink {
+                        monitor.stop();
+                    }
+                    drop(pending_guard);
+                    drop(sinks_guard);
+                    let _ = app.emit("audio-finished", instance_id);
+                    return;
                 }
-                
-                let duration_ms = duration.as_millis() as u64;
-                let mut paused_duration = Duration::from_secs(0);
-                let mut last_pause_start = None;
-                let mut last_processed_offset = base_offset;
-
-                loop {
-                    let (current_sink, current_start_time, current_base_offset) = {
-                        let sinks_guard = sinks.lock().unwrap();
-                        if let Some((_, _, s, _, _, st, bo, _)) = sinks_guard.get(&instance_id) {
-                            (Arc::clone(s), *st, *bo)
-                        } else {
-                            break;
-                        }
-                    };
 
-                    if current_base_offset != last_processed_offset {
-                        paused_duration = Duration::from_secs(0);
-                        last_processed_offset = current_base_offset;
-                        if current_sink.is_paused() {
-                            last_pause_start = Some(std::time::Instant::now());
-                        } else {
-                            last_pause_start = None;
-                        }
+                // Queued and starting instances of the group are cancelled too
+                if let Some(group) = &options.choke_group {
+                    let choked: Vec<u32> = sinks_guard.iter()
+                        .filter(|(_, instance)| instance.options.choke_group.as_ref() == Some(group))
+                        .map(|(other_id, _)| *other_id)
+                        .chain(pending_guard.iter()
+                            .filter(|(_, instance)| instance.choke_group.as_ref() == Some(group))
+                            .map(|(other_id, _)| *other_id))
+                        .collect();
+                    remove_instances(&mut sinks_guard, &mut pending_guard, &choked);
+                }
+
+                sinks_guard.insert(instance_id, ActiveInstance {
+                    id: id_clone.clone(),
+                    path: path_clone.clone(),
+                    is_opus,
+                    sink: Arc::clone(&sink),
+                    monitor_sink,
+                    volume,
+                    options: options.clone(),
+                    file_duration: duration,
+                    controls: Arc::clone(&controls),
+                    bus: bus.clone(),
+                    playhead,
+                    levels: levels.clone(),
+                });
+            }
+
+            // Progress is reported relative to the trimmed region
+            let duration_ms = options.region_duration(duration).as_millis() as u64;
+            let trim_start_ms = options.start_ms;
+
+            loop {
+                let (current_sink, position) = {
+                    let sinks_guard = sinks.lock().unwrap();
+                    if let Some(instance) = sinks_guard.get(&instance_id) {
+                        (Arc::clone(&instance.sink), instance.position())
+                    } else {
+                        break;
                     }
+                };
 
-                    if current_sink.is_paused() {
-                        if last_pause_start.is_none() {
-                            last_pause_start = Some(std::time::Instant::now());
-                        }
-                    } else if let Some(pause_start) = last_pause_start {
-                        paused_duration += pause_start.elapsed();
-                        last_pause_start = None;
+                let position_ms = (position.as_millis() as u64).saturating_sub(trim_start_ms);
+
+                let final_position = std::cmp::min(position_ms, duration_ms);
+
+                let _ = app.emit("audio-progress", AudioProgress {
+                    id: id_clone.clone(),
+                    instance_id,
+                    name: name_clone.clone(),
+                    position_ms: final_position,
+                    duration_ms,
+                    is_paused: current_sink.is_paused(),
+                    loop_count: controls.loop_count.load(Ordering::Relaxed),
+                    tempo: controls.tempo(),
+                });
+
+                std::thread::sleep(Duration::from_millis(30));
+
+                if current_sink.empty() {
+                    let sinks_check = sinks.lock().unwrap();
+                    if !sinks_check.contains_key(&instance_id) {
+                        break;
                     }
 
-                    let elapsed = if let Some(pause_start) = last_pause_start {
-                        pause_start.duration_since(current_start_time).saturating_sub(paused_duration)
-                    } else {
-                        current_start_time.elapsed().saturating_sub(paused_duration)
-                    };
-
-                    let position_ms = elapsed.as_millis() as u64 + current_base_offset.as_millis() as u64;
-
-                    let final_position = std::cmp::min(position_ms, duration_ms);
-                    
-                    let _ = app.emit("audio-progress", AudioProgress {
-                        id: id_clone.clone(),
-                        instance_id,
-                        name: name_clone.clone(),
-                        position_ms: final_position,
-                        duration_ms,
-                        is_paused: current_sink.is_paused(),
-                    });
-                    
-                    std::thread::sleep(Duration::from_millis(30));
-                    
-                    if current_sink.empty() { 
-                        let sinks_check = sinks.lock().unwrap();
-                        if !sinks_check.contains_key(&instance_id) {
-                            break; 
-                        }
-                        
-                        std::thread::sleep(Duration::from_millis(10));
-                        if let Some((_, _, final_sink, _, _, _, _, _)) = sinks_check.get(&instance_id) {
-                            if final_sink.empty() {
-                                break;
-                            }
-                        } else {
+                    std::thread::sleep(Duration::from_millis(10));
+                    if let Some(instance) = sinks_check.get(&instance_id) {
+                        if instance.sink.empty() {
                             break;
                         }
+                    } else {
+                        break;
                     }
                 }
+            }
 
-                {
-                    let mut sinks_guard = sinks.lock().unwrap();
-                    sinks_guard.remove(&instance_id);
-                }
-                
-                let _ = app.emit("audio-finished", instance_id);
+            {
+                let mut sinks_guard = sinks.lock().unwrap();
+                sinks_guard.remove(&instance_id);
             }
+
+            let _ = app.emit("audio-finished", instance_id);
+            return;
         }
+
+        // Decoding failed: release the reservation
+        pending.lock().unwrap().remove(&instance_id);
+        let _ = app.emit("audio-finished", instance_id);
     });
 
-    Ok(instance_id)
+    Ok(Some(instance_id))
 }
 
 #[tauri::command]
 pub async fn toggle_pause_instance(state: State<'_, AudioState>, instance_id: u32) -> Result<bool, String> {
     let sinks = state.sinks.lock().map_err(|_| "Failed to lock sinks")?;
-    if let Some((_, _, sink, _, _, _, _, _)) = sinks.get(&instance_id) {
-        if sink.is_paused() {
-            sink.play();
+    if let Some(instance) = sinks.get(&instance_id) {
+        if instance.is_paused() {
+            instance.play();
             Ok(false)
         } else {
-            sink.pause();
+            instance.pause();
             Ok(true)
         }
     } else {
@@ -896,11 +3568,31 @@ pub async fn toggle_pause_instance(state: State<'_, AudioState>, instance_id: u3
     }
 }
 
+/// Resumes every instance if all are paused, otherwise pauses the playing ones.
+/// Returns whether instances are now paused.
+#[tauri::command]
+pub async fn toggle_pause_all(state: State<'_, AudioState>) -> Result<bool, String> {
+    let sinks = state.sinks.lock().map_err(|_| "Failed to lock sinks")?;
+    let pause = sinks.values().any(|instance| !instance.is_paused());
+    for instance in sinks.values() {
+        if pause {
+            instance.pause();
+        } else {
+            instance.play();
+        }
+    }
+    Ok(pause)
+}
+
 #[tauri::command]
-pub async fn stop_instance(state: State<'_, AudioState>, instance_id: u32) -> Result<(), String> {
+pub async fn stop_instance(state: State<'_, AudioState>, instance_id: u32, fade_ms: Option<u64>) -> Result<(), String> {
     let mut sinks = state.sinks.lock().map_err(|_| "Failed to lock sinks")?;
-    if let Some((_, _, sink, _, _, _, _, _)) = sinks.remove(&instance_id) {
-        sink.stop();
+    state.pending.lock().map_err(|_| "Failed to lock pending instances")?.remove(&instance_id);
+    if let Some(instance) = sinks.get(&instance_id) {
+        // A fading instance stays registered until its progress loop sees it finish
+        if !instance.stop_with_fade(fade_ms.unwrap_or(0)) {
+            sinks.remove(&instance_id);
+        }
     }
     Ok(())
 }
@@ -909,69 +3601,118 @@ pub async fn stop_instance(state: State<'_, AudioState>, instance_id: u32) -> Re
 pub async fn seek_instance(state: State<'_, AudioState>, instance_id: u32, position_ms: u64) -> Result<(), String> {
     let mut sinks = state.sinks.lock().map_err(|_| "Failed to lock sinks")?;
     let master_vol = *state.master_volume.lock().unwrap();
+    let monitor_vol = *state.monitor_volume.lock().unwrap();
 
-    if let Some((_, path, sink, volume, _, start_time, base_offset, levels)) = sinks.get_mut(&instance_id) {
-        let was_paused = sink.is_paused();
-        sink.stop();
-        
-        let handle = state.get_or_create_stream_handle(&state.current_device_name.lock().unwrap())?;
-        if let Ok(new_sink) = Sink::try_new(&handle) {
-            let new_sink = Arc::new(new_sink);
-            
-            if let Ok(source) = SymphoniaSource::new(path, Some(Duration::from_millis(position_ms))) {
-                let new_levels = Arc::new(Mutex::new(LevelData {
-                    peak: 0.0,
-                    rms: 0.0,
-                    volume: *volume,
-                    last_update: Instant::now(),
-                }));
-                
-                let metered_source = LevelMeter::new(source, new_levels.clone());
-                new_sink.append(metered_source);
-                
-                state.meter_manager.add_meter(new_levels.clone(), Arc::clone(&new_sink));
-                
-                new_sink.set_volume(*volume * master_vol);
-                
-                if was_paused {
-                    new_sink.pause();
-                }
+    if let Some(instance) = sinks.get_mut(&instance_id) {
+        let was_paused = instance.is_paused();
+        instance.stop();
 
-                *sink = new_sink;
-                *start_time = std::time::Instant::now();
-                *base_offset = Duration::from_millis(position_ms);
-                *levels = new_levels;
+        let (bus, monitor_bus) = state.instance_buses(instance.options.device.as_deref())?;
+        // Seek positions are relative to the trimmed region
+        let mut position = instance.options.trim_start() + Duration::from_millis(position_ms);
+        if let Some(end) = instance.options.trim_end() {
+            position = position.min(end);
+        }
+
+        if let Ok(source) = open_playback_source(&state.samples, &instance.path, Some(position), instance.is_opus) {
+            let new_sink = Arc::new(bus.new_sink());
+            if was_paused {
+                new_sink.pause();
             }
+            let playhead = source.playhead();
+            let new_levels = Arc::new(Mutex::new(LevelData {
+                peak: 0.0,
+                rms: 0.0,
+                volume: instance.volume,
+                last_update: Instant::now(),
+            }));
+
+            let processed = instance.options.wrap(source, position, instance.file_duration, &state.ducking, &instance.controls);
+            let metered_source = LevelMeter::new(processed, new_levels.clone());
+            new_sink.append(metered_source);
+
+            state.meter_manager.add_meter(new_levels.clone(), Arc::clone(&new_sink), !instance.options.duckable);
+
+            let new_monitor = monitor_bus.as_ref().and_then(|b| {
+                let sink = b.new_sink();
+                if was_paused {
+                    sink.pause();
+                }
+                let source = open_playback_source(&state.samples, &instance.path, Some(position), instance.is_opus).ok()?;
+                sink.append(instance.options.wrap(source, position, instance.file_duration, &state.ducking, &instance.controls));
+                Some(Arc::new(sink))
+            });
+
+            instance.sink = new_sink;
+            instance.monitor_sink = new_monitor;
+            instance.bus = bus;
+            instance.apply_volume(master_vol, monitor_vol);
+            instance.playhead = playhead;
+            instance.levels = new_levels;
         }
     }
     Ok(())
 }
 
+/// Changes pitch and/or tempo of a running instance; omitted values are kept.
+#[tauri::command]
+pub async fn update_instance_rate(
+    state: State<'_, AudioState>,
+    instance_id: u32,
+    pitch_semitones: Option<f32>,
+    tempo: Option<f32>,
+) -> Result<(), String> {
+    let sinks = state.sinks.lock().map_err(|_| "Failed to lock sinks")?;
+    let instance = sinks.get(&instance_id).ok_or("Instance not found")?;
+
+    let pitch_semitones = pitch_semitones.unwrap_or_else(|| instance.controls.pitch_semitones());
+    let tempo = tempo.unwrap_or_else(|| instance.controls.tempo());
+    validate_rate(pitch_semitones, tempo)?;
+
+    instance.controls.set_rate(pitch_semitones, tempo);
+    Ok(())
+}
+
 #[tauri::command]
-pub async fn stop_all(state: State<'_, AudioState>) -> Result<(), String> {
+pub async fn stop_all(state: State<'_, AudioState>, fade_ms: Option<u64>) -> Result<(), String> {
     let mut sinks = state.sinks.lock().map_err(|_| "Failed to lock sinks")?;
-    for (_, (_, _, sink, _, _, _, _, _)) in sinks.iter() {
-        sink.stop();
-    }
-    sinks.clear();
+    state.pending.lock().map_err(|_| "Failed to lock pending instances")?.clear();
+    let fade_ms = fade_ms.unwrap_or(0);
+    sinks.retain(|_, instance| instance.stop_with_fade(fade_ms));
     Ok(())
 }
 
 #[tauri::command]
 pub async fn update_button_volume(state: State<'_, AudioState>, button_id: String, volume: f32) -> Result<(), String> {
     let master_vol = *state.master_volume.lock().map_err(|_| "Failed to lock master volume")?;
+    let monitor_vol = *state.monitor_volume.lock().map_err(|_| "Failed to lock monitor volume")?;
     let mut sinks = state.sinks.lock().map_err(|_| "Failed to lock sinks")?;
-    
-    for (_instance_id, (id, _, sink, stored_vol, _, _, _, levels)) in sinks.iter_mut() {
-        if *id == button_id {
-            *stored_vol = volume;
-            sink.set_volume(volume * master_vol);
-            if let Ok(mut levels_data) = levels.lock() {
+
+    for instance in sinks.values_mut() {
+        if instance.id == button_id {
+            instance.volume = volume;
+            instance.apply_volume(master_vol, monitor_vol);
+            if let Ok(mut levels_data) = instance.levels.lock() {
                 levels_data.volume = volume;
             }
         }
     }
-    
+
+    Ok(())
+}
+
+/// Replaces the effect chain of every playing instance of a button.
+#[tauri::command]
+pub async fn update_button_effects(state: State<'_, AudioState>, button_id: String, effects: EffectsSettings) -> Result<(), String> {
+    let sinks = state.sinks.lock().map_err(|_| "Failed to lock sinks")?;
+
+    // Main and monitor chains share the controls, so both follow the change
+    for instance in sinks.values() {
+        if instance.id == button_id {
+            instance.controls.set_effects(effects.clone());
+        }
+    }
+
     Ok(())
 }
 
@@ -0,0 +1,88 @@
+use crate::audio::{self, AudioState, PlaybackOptions};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Commands accepted from remote control surfaces. Each one runs the same code as the
+/// matching Tauri command.
+#[derive(Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum ControlCommand {
+    /// Plays a board button with its saved settings, like its global shortcut.
+    TriggerButton { button_id: u32 },
+    Play {
+        id: String,
+        path: String,
+        #[serde(default)]
+        name: Option<String>,
+        #[serde(default)]
+        volume: Option<f32>,
+        #[serde(default)]
+        options: Option<Box<PlaybackOptions>>,
+    },
+    Stop {
+        instance_id: u32,
+        #[serde(default)]
+        fade_ms: Option<u64>,
+    },
+    StopAll {
+        #[serde(default)]
+        fade_ms: Option<u64>,
+    },
+    TogglePause { instance_id: u32 },
+    Seek { instance_id: u32, position_ms: u64 },
+    SetMasterVolume { volume: f32 },
+    SetButtonVolume { button_id: String, volume: f32 },
+    ListDevices,
+    SetDevice { device_id: String },
+}
+
+/// Runs `command` and returns its result as JSON.
+pub fn execute(app: &AppHandle, command: ControlCommand) -> Result<Value, String> {
+    let state = app.state::<AudioState>();
+    tauri::async_runtime::block_on(async {
+        match command {
+            ControlCommand::TriggerButton { button_id } => {
+                app.emit("global-shortcut-triggered", button_id).map_err(|e| e.to_string())?;
+                Ok(Value::Null)
+            }
+            ControlCommand::Play { id, path, name, volume, options } => {
+                let name = name.unwrap_or_else(|| id.clone());
+                let instance_id = audio::play_sound(app.clone(), state, id, path, name, volume.unwrap_or(1.0), options.map(|o| *o)).await?;
+                Ok(json!(instance_id))
+            }
+            ControlCommand::Stop { instance_id, fade_ms } => {
+                audio::stop_instance(state, instance_id, fade_ms).await.map(|_| Value::Null)
+            }
+            ControlCommand::StopAll { fade_ms } => audio::stop_all(state, fade_ms).await.map(|_| Value::Null),
+            ControlCommand::TogglePause { instance_id } => {
+                audio::toggle_pause_instance(state, instance_id).await.map(|paused| json!(paused))
+            }
+            ControlCommand::Seek { instance_id, position_ms } => {
+                audio::seek_instance(state, instance_id, position_ms).await.map(|_| Value::Null)
+            }
+            ControlCommand::SetMasterVolume { volume } => {
+                let volume = volume.clamp(0.0, 1.0);
+                audio::update_master_volume(state, volume).await?;
+                // Keeps the slider in the window in sync
+                let _ = app.emit("master-volume-changed", volume);
+                Ok(Value::Null)
+            }
+            ControlCommand::SetButtonVolume { button_id, volume } => {
+                audio::update_button_volume(state, button_id, volume).await.map(|_| Value::Null)
+            }
+            ControlCommand::ListDevices => {
+                let devices = audio::list_audio_devices(state).await?;
+                serde_json::to_value(devices).map_err(|e| e.to_string())
+            }
+            ControlCommand::SetDevice { device_id } => {
+                let selection = audio::set_audio_device(state, device_id).await?;
+                // A suggested stand-in is not saved as the user's choice
+                if !selection.suggested {
+                    let _ = app.emit("output-device-changed", &selection.id);
+                }
+                serde_json::to_value(selection).map_err(|e| e.to_string())
+            }
+        }
+    })
+}
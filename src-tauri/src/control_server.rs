@@ -0,0 +1,297 @@
+//! Localhost HTTP/WebSocket control API.
+//!
+//! `POST /api/command` takes one `ControlCommand` as JSON. `GET /ws` upgrades to a
+//! WebSocket that accepts the same commands, echoing an optional `request_id` in each
+//! `{"type":"response"}`, and pushes `audio-progress`, `audio-finished` and `master-level`
+//! as `{"type":"event"}` messages. Every request carries the token in
+//! `Authorization: Bearer` or `?token=`.
+
+use crate::control::{self, ControlCommand};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tauri::{AppHandle, EventId, Listener, State};
+use tungstenite::protocol::Role;
+use tungstenite::{Message, WebSocket};
+
+/// Backend events pushed to every WebSocket client.
+const FORWARDED_EVENTS: [&str; 3] = ["audio-progress", "audio-finished", "master-level"];
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+const MAX_HEAD_BYTES: usize = 16 * 1024;
+const MAX_BODY_BYTES: usize = 64 * 1024;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ControlServerSettings {
+    pub enabled: bool,
+    pub port: u16,
+    /// Required from every client; generated when empty.
+    pub token: String,
+}
+
+impl Default for ControlServerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 7373,
+            token: String::new(),
+        }
+    }
+}
+
+type Clients = Arc<Mutex<Vec<Sender<String>>>>;
+
+struct RunningServer {
+    app: AppHandle,
+    stop: Arc<AtomicBool>,
+    listeners: Vec<EventId>,
+    accept_thread: JoinHandle<()>,
+}
+
+impl RunningServer {
+    fn start(app: &AppHandle, settings: &ControlServerSettings) -> Result<Self, String> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, settings.port))
+            .map_err(|e| format!("Failed to listen on port {}: {}", settings.port, e))?;
+        // Non-blocking so the accept loop notices when it is stopped
+        listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let clients: Clients = Arc::new(Mutex::new(Vec::new()));
+        let listeners = FORWARDED_EVENTS
+            .iter()
+            .map(|name| {
+                let clients = Arc::clone(&clients);
+                app.listen_any(*name, move |event| {
+                    let payload: Value = serde_json::from_str(event.payload()).unwrap_or(Value::Null);
+                    let message = json!({ "type": "event", "event": name, "payload": payload }).to_string();
+                    if let Ok(mut clients) = clients.lock() {
+                        clients.retain(|client| client.send(message.clone()).is_ok());
+                    }
+                })
+            })
+            .collect();
+
+        let app_clone = app.clone();
+        let stop_clone = Arc::clone(&stop);
+        let token = settings.token.clone();
+        let accept_thread = std::thread::spawn(move || {
+            while !stop_clone.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        let app = app_clone.clone();
+                        let stop = Arc::clone(&stop_clone);
+                        let clients = Arc::clone(&clients);
+                        let token = token.clone();
+                        std::thread::spawn(move || {
+                            let _ = handle_connection(&app, stream, &token, &clients, &stop);
+                        });
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => std::thread::sleep(POLL_INTERVAL),
+                    Err(_) => std::thread::sleep(POLL_INTERVAL),
+                }
+            }
+        });
+
+        Ok(Self { app: app.clone(), stop, listeners, accept_thread })
+    }
+
+    /// Closes the listener and every open WebSocket. Returns once the port is free again.
+    fn stop(self) {
+        self.stop.store(true, Ordering::Relaxed);
+        for id in self.listeners {
+            self.app.unlisten(id);
+        }
+        let _ = self.accept_thread.join();
+    }
+}
+
+/// Optional localhost WebSocket/HTTP API, managed as Tauri state.
+#[derive(Default)]
+pub struct ControlServer {
+    running: Mutex<Option<RunningServer>>,
+}
+
+/// 128-bit token from the OS CSPRNG, as hex.
+fn generate_token() -> Result<String, String> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).map_err(|e| format!("Failed to generate token: {}", e))?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Compares every byte whatever the first mismatch, so response times do not leak the token.
+fn token_matches(given: &str, token: &str) -> bool {
+    given.len() == token.len() && given.bytes().zip(token.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+fn header<'a>(request: &'a httparse::Request, name: &str) -> Option<&'a str> {
+    request.headers.iter()
+        .find(|h| h.name.eq_ignore_ascii_case(name))
+        .and_then(|h| std::str::from_utf8(h.value).ok())
+}
+
+fn query_param<'a>(query: &'a str, name: &str) -> Option<&'a str> {
+    query.split('&').find_map(|pair| pair.strip_prefix(name)?.strip_prefix('='))
+}
+
+fn write_response(stream: &mut TcpStream, status: &str, body: &Value) -> io::Result<()> {
+    let body = body.to_string();
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+fn command_reply(app: &AppHandle, command: Value) -> (bool, Value) {
+    let result = serde_json::from_value::<ControlCommand>(command)
+        .map_err(|e| format!("Invalid command: {}", e))
+        .and_then(|command| control::execute(app, command));
+    match result {
+        Ok(result) => (true, json!({ "ok": true, "result": result })),
+        Err(error) => (false, json!({ "ok": false, "error": error })),
+    }
+}
+
+/// Serves one request: `POST /api/command` with a command as JSON body, or `GET /ws` to
+/// upgrade to a WebSocket. The token goes in `Authorization: Bearer` or `?token=`.
+fn handle_connection(app: &AppHandle, mut stream: TcpStream, token: &str, clients: &Clients, stop: &AtomicBool) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let mut head = Vec::new();
+    while !head.ends_with(b"\r\n\r\n") {
+        if reader.read_until(b'\n', &mut head)? == 0 || head.len() > MAX_HEAD_BYTES {
+            return Ok(());
+        }
+    }
+
+    let mut headers = [httparse::EMPTY_HEADER; 32];
+    let mut request = httparse::Request::new(&mut headers);
+    if !matches!(request.parse(&head), Ok(httparse::Status::Complete(_))) {
+        return write_response(&mut stream, "400 Bad Request", &json!({ "ok": false, "error": "Malformed request" }));
+    }
+
+    let (path, query) = request.path.unwrap_or("/").split_once('?').unwrap_or((request.path.unwrap_or("/"), ""));
+    let bearer = header(&request, "Authorization").and_then(|v| v.strip_prefix("Bearer "));
+    if !bearer.or(query_param(query, "token")).is_some_and(|given| token_matches(given, token)) {
+        return write_response(&mut stream, "401 Unauthorized", &json!({ "ok": false, "error": "Invalid token" }));
+    }
+
+    match (request.method.unwrap_or(""), path) {
+        ("GET", "/ws") => {
+            let key = header(&request, "Sec-WebSocket-Key")
+                .filter(|_| header(&request, "Upgrade").is_some_and(|v| v.eq_ignore_ascii_case("websocket")));
+            let Some(key) = key else {
+                return write_response(&mut stream, "400 Bad Request", &json!({ "ok": false, "error": "Expected a WebSocket upgrade" }));
+            };
+            write!(
+                stream,
+                "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+                tungstenite::handshake::derive_accept_key(key.as_bytes())
+            )?;
+
+            let (sender, events) = mpsc::channel();
+            if let Ok(mut clients) = clients.lock() {
+                clients.push(sender);
+            }
+            serve_websocket(app, stream, events, stop)
+        }
+        ("POST", "/api/command") => {
+            let length = header(&request, "Content-Length").and_then(|v| v.trim().parse::<usize>().ok()).unwrap_or(0);
+            if length > MAX_BODY_BYTES {
+                return write_response(&mut stream, "413 Payload Too Large", &json!({ "ok": false, "error": "Body too large" }));
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body)?;
+
+            let (status, reply) = match serde_json::from_slice::<Value>(&body) {
+                Ok(command) => match command_reply(app, command) {
+                    (true, reply) => ("200 OK", reply),
+                    (false, reply) => ("400 Bad Request", reply),
+                },
+                Err(e) => ("400 Bad Request", json!({ "ok": false, "error": format!("Invalid JSON: {}", e) })),
+            };
+            write_response(&mut stream, status, &reply)
+        }
+        _ => write_response(&mut stream, "404 Not Found", &json!({ "ok": false, "error": "Not found" })),
+    }
+}
+
+/// Answers command messages (with an optional `request_id` echoed back) and pushes events
+/// until the client leaves or the server stops.
+fn serve_websocket(app: &AppHandle, stream: TcpStream, events: Receiver<String>, stop: &AtomicBool) -> io::Result<()> {
+    // Short reads let one thread both answer commands and forward events
+    stream.set_read_timeout(Some(POLL_INTERVAL))?;
+    let mut socket = WebSocket::from_raw_socket(stream, Role::Server, None);
+
+    while !stop.load(Ordering::Relaxed) {
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                let reply = match serde_json::from_str::<Value>(&text) {
+                    Ok(command) => {
+                        let request_id = command.get("request_id").cloned().unwrap_or(Value::Null);
+                        let (_, mut reply) = command_reply(app, command);
+                        reply["type"] = json!("response");
+                        reply["request_id"] = request_id;
+                        reply
+                    }
+                    Err(e) => json!({ "type": "response", "ok": false, "error": format!("Invalid JSON: {}", e) }),
+                };
+                if socket.send(Message::Text(reply.to_string())).is_err() {
+                    return Ok(());
+                }
+            }
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e)) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {}
+            Err(_) => return Ok(()),
+        }
+
+        loop {
+            match events.try_recv() {
+                Ok(message) => {
+                    if socket.send(Message::Text(message)).is_err() {
+                        return Ok(());
+                    }
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return Ok(()),
+            }
+        }
+    }
+
+    let _ = socket.close(None);
+    let _ = socket.flush();
+    Ok(())
+}
+
+/// Starts, restarts or stops the control server. Returns the settings in effect,
+/// including the generated token when none was given.
+#[tauri::command]
+pub async fn update_control_server(
+    app: AppHandle,
+    server: State<'_, ControlServer>,
+    settings: ControlServerSettings,
+) -> Result<ControlServerSettings, String> {
+    let mut settings = settings;
+    if settings.token.is_empty() {
+        settings.token = generate_token()?;
+    }
+
+    let mut running = server.running.lock().map_err(|_| "Failed to lock control server")?;
+    if let Some(previous) = running.take() {
+        previous.stop();
+    }
+    if settings.enabled {
+        *running = Some(RunningServer::start(&app, &settings)?);
+    }
+    Ok(settings)
+}
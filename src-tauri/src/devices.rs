@@ -0,0 +1,247 @@
+use cpal::traits::{DeviceTrait, HostTrait};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+
+/// Id of the pseudo-device that follows the system default output.
+pub const DEFAULT_DEVICE: &str = "Default";
+
+const COMMON_SAMPLE_RATES: [u32; 11] = [8000, 11025, 16000, 22050, 32000, 44100, 48000, 88200, 96000, 176400, 192000];
+
+/// Minimum similarity for a saved device to be matched to a renamed one. Sibling
+/// endpoints ("CABLE-A Input" / "CABLE-B Input") score up to about 0.87.
+const FUZZY_THRESHOLD: f32 = 0.9;
+/// How far the best match must lead the runner-up.
+const FUZZY_MARGIN: f32 = 0.1;
+
+/// An output device as shown to the frontend.
+#[derive(Clone, Serialize)]
+pub struct DeviceInfo {
+    /// `host:name`, with `#2`, `#3`... appended to identical names in enumeration order.
+    pub id: String,
+    pub host: String,
+    pub name: String,
+    /// The system default output.
+    pub is_default: bool,
+    pub sample_rates: Vec<u32>,
+    pub channels: Vec<u16>,
+}
+
+/// Hides raw hardware and plugin PCMs that ALSA lists next to the usable devices.
+/// Other hosts only list real endpoints.
+pub fn is_listed_device(host: cpal::HostId, name: &str) -> bool {
+    if host.name() != "ALSA" {
+        return true;
+    }
+    let n = name.to_lowercase();
+    !n.starts_with("hw:") &&
+    !n.starts_with("plughw:") &&
+    !n.starts_with("dmix:") &&
+    !n.starts_with("dsnoop:") &&
+    !n.ends_with("rate") &&
+    !n.starts_with("speex") &&
+    !n.contains("surround") &&
+    !n.contains("upmix") &&
+    !n.contains("vdownmix")
+}
+
+/// Output devices of `host` paired with their ids.
+pub fn output_devices(host: &cpal::Host) -> Result<Vec<(String, cpal::Device)>, String> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let devices = host.output_devices().map_err(|e| e.to_string())?;
+    Ok(devices
+        .filter_map(|device| {
+            let name = device.name().ok()?;
+            let count = seen.entry(name.clone()).or_default();
+            *count += 1;
+            let id = match *count {
+                1 => format!("{}:{}", host.id().name(), name),
+                n => format!("{}:{}#{}", host.id().name(), name, n),
+            };
+            Some((id, device))
+        })
+        .collect())
+}
+
+/// Looks up a device by exact id.
+pub fn find_output_device(host: &cpal::Host, id: &str) -> Option<cpal::Device> {
+    if id == DEFAULT_DEVICE {
+        return host.default_output_device();
+    }
+    output_devices(host).ok()?
+        .into_iter()
+        .find(|(device_id, _)| device_id == id)
+        .map(|(_, device)| device)
+}
+
+pub fn describe(host: &cpal::Host, id: String, device: &cpal::Device) -> DeviceInfo {
+    let name = if id == DEFAULT_DEVICE {
+        DEFAULT_DEVICE.to_string()
+    } else {
+        device.name().unwrap_or_default()
+    };
+    let default_name = host.default_output_device().and_then(|d| d.name().ok());
+
+    let ranges: Vec<_> = device.supported_output_configs().map(|c| c.collect()).unwrap_or_default();
+    let sample_rates = COMMON_SAMPLE_RATES
+        .into_iter()
+        .filter(|rate| ranges.iter().any(|r| (r.min_sample_rate().0..=r.max_sample_rate().0).contains(rate)))
+        .collect();
+    let channels = ranges.iter().map(|r| r.channels()).collect::<BTreeSet<_>>().into_iter().collect();
+
+    DeviceInfo {
+        is_default: id != DEFAULT_DEVICE && default_name.as_deref() == Some(name.as_str()),
+        id,
+        host: host.id().name().to_string(),
+        name,
+        sample_rates,
+        channels,
+    }
+}
+
+/// Device name inside an id. Plain names saved before ids existed are returned as is.
+fn name_of(id: &str) -> &str {
+    let name = cpal::available_hosts()
+        .into_iter()
+        .find_map(|host| id.strip_prefix(host.name()).and_then(|rest| rest.strip_prefix(':')))
+        .unwrap_or(id);
+    match name.rsplit_once('#') {
+        Some((base, n)) if !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()) => base,
+        _ => name,
+    }
+}
+
+fn normalize(name: &str) -> Vec<char> {
+    name.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+/// Dice coefficient over character bigrams, 0..=1.
+fn similarity(a: &str, b: &str) -> f32 {
+    let (a, b) = (normalize(a), normalize(b));
+    if a == b {
+        return 1.0;
+    }
+    if a.len() < 2 || b.len() < 2 {
+        return 0.0;
+    }
+    let mut bigrams: HashMap<(char, char), usize> = HashMap::new();
+    for pair in a.windows(2) {
+        *bigrams.entry((pair[0], pair[1])).or_default() += 1;
+    }
+    let mut shared = 0;
+    for pair in b.windows(2) {
+        if let Some(count) = bigrams.get_mut(&(pair[0], pair[1])).filter(|c| **c > 0) {
+            *count -= 1;
+            shared += 1;
+        }
+    }
+    2.0 * shared as f32 / (a.len() + b.len() - 2) as f32
+}
+
+/// A device picked for a saved id.
+#[derive(Clone, Serialize)]
+pub struct DeviceSelection {
+    /// Device now in use.
+    pub id: String,
+    /// `id` only has a name similar to the saved device, which is missing. The frontend
+    /// keeps the saved id and can offer this one instead of persisting it.
+    pub suggested: bool,
+}
+
+/// Matches a saved id against `(id, name)` pairs: the id itself, a device with the same
+/// name, or else a single clearly most similar `listed` name.
+fn resolve(saved: &str, devices: &[(String, String)], listed: impl Fn(&str) -> bool) -> Option<DeviceSelection> {
+    let found = |id: &str| DeviceSelection { id: id.to_string(), suggested: false };
+    if devices.iter().any(|(id, _)| id == saved) {
+        return Some(found(saved));
+    }
+
+    let wanted = name_of(saved);
+    if let Some((id, _)) = devices.iter().find(|(_, name)| name == wanted) {
+        return Some(found(id));
+    }
+
+    let mut scores: Vec<(&String, f32)> = devices.iter()
+        .filter(|(_, name)| listed(name))
+        .map(|(id, name)| (id, similarity(wanted, name)))
+        .collect();
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+    match scores.as_slice() {
+        [(id, best), rest @ ..] if *best >= FUZZY_THRESHOLD
+            && rest.first().is_none_or(|(_, second)| best - second >= FUZZY_MARGIN) =>
+        {
+            Some(DeviceSelection { id: id.to_string(), suggested: true })
+        }
+        _ => None,
+    }
+}
+
+/// Resolves a saved id to a current device, see [`DeviceSelection`].
+pub fn resolve_output_id(host: &cpal::Host, saved: &str) -> Result<DeviceSelection, String> {
+    if saved == DEFAULT_DEVICE {
+        return Ok(DeviceSelection { id: saved.to_string(), suggested: false });
+    }
+    let devices: Vec<(String, String)> = output_devices(host)?
+        .into_iter()
+        .filter_map(|(id, device)| Some((id, device.name().ok()?)))
+        .collect();
+    resolve(saved, &devices, |name| is_listed_device(host.id(), name)).ok_or("Audio device not found".to_string())
+}
+
+/// Display name for an id, used in device events.
+pub fn display_name(id: &str) -> String {
+    name_of(id).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(name: &str) -> String {
+        format!("{}:{}", cpal::default_host().id().name(), name)
+    }
+
+    fn devices(names: &[&str]) -> Vec<(String, String)> {
+        names.iter().map(|name| (id(name), name.to_string())).collect()
+    }
+
+    #[test]
+    fn similarity_scores_sibling_endpoints_below_threshold() {
+        assert!(similarity("Speakers (Realtek(R) Audio)", "Headphones (Realtek(R) Audio)") < FUZZY_THRESHOLD);
+        assert!(similarity("CABLE Input", "CABLE-A Input") < FUZZY_THRESHOLD);
+        assert!(similarity("CABLE-A Input (VB-Audio Cable A)", "CABLE-B Input (VB-Audio Cable B)") < FUZZY_THRESHOLD);
+        assert!(similarity("Speakers (Realtek(R) Audio)", "Speakers (2- Realtek(R) Audio)") >= FUZZY_THRESHOLD);
+    }
+
+    #[test]
+    fn resolve_keeps_exact_and_same_name_matches() {
+        let list = devices(&["Speakers (Realtek(R) Audio)"]);
+        let exact = resolve(&id("Speakers (Realtek(R) Audio)"), &list, |_| true).unwrap();
+        assert_eq!(exact.id, id("Speakers (Realtek(R) Audio)"));
+        assert!(!exact.suggested);
+        // Names saved before ids existed
+        let by_name = resolve("Speakers (Realtek(R) Audio)", &list, |_| true).unwrap();
+        assert!(!by_name.suggested);
+    }
+
+    #[test]
+    fn resolve_does_not_swap_to_a_different_device() {
+        let list = devices(&["Headphones (Realtek(R) Audio)"]);
+        assert!(resolve(&id("Speakers (Realtek(R) Audio)"), &list, |_| true).is_none());
+        let list = devices(&["CABLE-A Input"]);
+        assert!(resolve(&id("CABLE Input"), &list, |_| true).is_none());
+    }
+
+    #[test]
+    fn resolve_suggests_a_renamed_device() {
+        let list = devices(&["Speakers (2- Realtek(R) Audio)", "Headphones (Realtek(R) Audio)"]);
+        let selection = resolve(&id("Speakers (Realtek(R) Audio)"), &list, |_| true).unwrap();
+        assert_eq!(selection.id, id("Speakers (2- Realtek(R) Audio)"));
+        assert!(selection.suggested);
+    }
+
+    #[test]
+    fn resolve_rejects_ambiguous_matches() {
+        let list = devices(&["Speakers (2- Realtek(R) Audio)", "Speakers (3- Realtek(R) Audio)"]);
+        assert!(resolve(&id("Speakers (Realtek(R) Audio)"), &list, |_| true).is_none());
+    }
+}
@@ -0,0 +1,330 @@
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+/// Second-order IIR section (transposed direct form II), coefficients normalized by a0.
+#[derive(Clone, Copy, Default)]
+pub struct Biquad {
+    pub b0: f64,
+    pub b1: f64,
+    pub b2: f64,
+    pub a1: f64,
+    pub a2: f64,
+    pub z1: f64,
+    pub z2: f64,
+}
+
+impl Biquad {
+    fn from_raw(b0: f64, b1: f64, b2: f64, a0: f64, a1: f64, a2: f64) -> Self {
+        Self {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+            ..Default::default()
+        }
+    }
+
+    pub fn process(&mut self, x: f64) -> f64 {
+        let y = self.b0 * x + self.z1;
+        self.z1 = self.b1 * x - self.a1 * y + self.z2;
+        self.z2 = self.b2 * x - self.a2 * y;
+        y
+    }
+}
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EqBandKind {
+    #[default]
+    Peaking,
+    LowShelf,
+    HighShelf,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EqBand {
+    pub kind: EqBandKind,
+    pub frequency: f32,
+    pub gain_db: f32,
+    pub q: f32,
+}
+
+impl Default for EqBand {
+    fn default() -> Self {
+        Self {
+            kind: EqBandKind::Peaking,
+            frequency: 1000.0,
+            gain_db: 0.0,
+            q: 0.707,
+        }
+    }
+}
+
+/// High- or low-pass cutoff.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PassFilter {
+    pub frequency: f32,
+    pub q: f32,
+}
+
+impl Default for PassFilter {
+    fn default() -> Self {
+        Self {
+            frequency: 100.0,
+            q: 0.707,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompressorSettings {
+    pub threshold_db: f32,
+    pub ratio: f32,
+    pub attack_ms: f32,
+    pub release_ms: f32,
+    pub knee_db: f32,
+    pub makeup_db: f32,
+}
+
+impl Default for CompressorSettings {
+    fn default() -> Self {
+        Self {
+            threshold_db: -18.0,
+            ratio: 4.0,
+            attack_ms: 10.0,
+            release_ms: 120.0,
+            knee_db: 6.0,
+            makeup_db: 0.0,
+        }
+    }
+}
+
+/// Per-button effects, applied in order: high-pass, low-pass, EQ bands, compressor, gain trim.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EffectsSettings {
+    pub gain_db: f32,
+    pub high_pass: Option<PassFilter>,
+    pub low_pass: Option<PassFilter>,
+    pub eq: Vec<EqBand>,
+    pub compressor: Option<CompressorSettings>,
+}
+
+impl EffectsSettings {
+    /// True when the chain would leave the signal untouched.
+    pub fn is_neutral(&self) -> bool {
+        self.gain_db == 0.0
+            && self.high_pass.is_none()
+            && self.low_pass.is_none()
+            && self.eq.iter().all(|b| b.gain_db == 0.0)
+            && self.compressor.is_none()
+    }
+}
+
+enum FilterShape {
+    HighPass,
+    LowPass,
+    Band(EqBandKind, f64),
+}
+
+/// RBJ audio-EQ-cookbook designs.
+fn design(shape: FilterShape, frequency: f32, q: f32, sample_rate: f64) -> Biquad {
+    let frequency = (frequency as f64).clamp(10.0, sample_rate * 0.49);
+    let w0 = 2.0 * PI * frequency / sample_rate;
+    let (sin, cos) = w0.sin_cos();
+    let alpha = sin / (2.0 * (q as f64).max(0.05));
+
+    match shape {
+        FilterShape::HighPass => Biquad::from_raw(
+            (1.0 + cos) / 2.0,
+            -(1.0 + cos),
+            (1.0 + cos) / 2.0,
+            1.0 + alpha,
+            -2.0 * cos,
+            1.0 - alpha,
+        ),
+        FilterShape::LowPass => Biquad::from_raw(
+            (1.0 - cos) / 2.0,
+            1.0 - cos,
+            (1.0 - cos) / 2.0,
+            1.0 + alpha,
+            -2.0 * cos,
+            1.0 - alpha,
+        ),
+        FilterShape::Band(kind, gain_db) => {
+            let a = 10f64.powf(gain_db / 40.0);
+            let shelf = 2.0 * a.sqrt() * alpha;
+            match kind {
+                EqBandKind::Peaking => Biquad::from_raw(
+                    1.0 + alpha * a,
+                    -2.0 * cos,
+                    1.0 - alpha * a,
+                    1.0 + alpha / a,
+                    -2.0 * cos,
+                    1.0 - alpha / a,
+                ),
+                EqBandKind::LowShelf => Biquad::from_raw(
+                    a * ((a + 1.0) - (a - 1.0) * cos + shelf),
+                    2.0 * a * ((a - 1.0) - (a + 1.0) * cos),
+                    a * ((a + 1.0) - (a - 1.0) * cos - shelf),
+                    (a + 1.0) + (a - 1.0) * cos + shelf,
+                    -2.0 * ((a - 1.0) + (a + 1.0) * cos),
+                    (a + 1.0) + (a - 1.0) * cos - shelf,
+                ),
+                EqBandKind::HighShelf => Biquad::from_raw(
+                    a * ((a + 1.0) + (a - 1.0) * cos + shelf),
+                    -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+                    a * ((a + 1.0) + (a - 1.0) * cos - shelf),
+                    (a + 1.0) - (a - 1.0) * cos + shelf,
+                    2.0 * ((a - 1.0) - (a + 1.0) * cos),
+                    (a + 1.0) - (a - 1.0) * cos - shelf,
+                ),
+            }
+        }
+    }
+}
+
+/// Feed-forward soft-knee compressor, linked across channels.
+struct Compressor {
+    settings: CompressorSettings,
+    attack_coeff: f32,
+    release_coeff: f32,
+    envelope: f32,
+    gain: f32,
+}
+
+impl Compressor {
+    fn new(settings: CompressorSettings, channels: usize, sample_rate: f64) -> Self {
+        let mut compressor = Self {
+            settings,
+            attack_coeff: 0.0,
+            release_coeff: 0.0,
+            envelope: 0.0,
+            gain: 1.0,
+        };
+        compressor.configure(settings, channels, sample_rate);
+        compressor
+    }
+
+    fn configure(&mut self, settings: CompressorSettings, channels: usize, sample_rate: f64) {
+        // The detector runs on every interleaved sample, hence the channel factor
+        let samples_per_ms = (sample_rate * channels as f64 / 1000.0) as f32;
+        self.attack_coeff = (-1.0 / (settings.attack_ms.max(0.1) * samples_per_ms)).exp();
+        self.release_coeff = (-1.0 / (settings.release_ms.max(1.0) * samples_per_ms)).exp();
+        self.settings = settings;
+    }
+
+    fn detect(&mut self, sample: f32) {
+        let level = sample.abs();
+        let coeff = if level > self.envelope { self.attack_coeff } else { self.release_coeff };
+        self.envelope = level + (self.envelope - level) * coeff;
+    }
+
+    /// Recomputes the gain from the detector; called once per frame.
+    fn update_gain(&mut self) {
+        let s = &self.settings;
+        let level_db = 20.0 * self.envelope.max(1e-6).log10();
+        let over = level_db - s.threshold_db;
+        let slope = 1.0 / s.ratio.max(1.0) - 1.0;
+        let knee = s.knee_db.max(0.0);
+
+        let reduction_db = if knee > 0.0 && 2.0 * over.abs() <= knee {
+            slope * (over + knee / 2.0).powi(2) / (2.0 * knee)
+        } else if over > 0.0 {
+            slope * over
+        } else {
+            0.0
+        };
+        self.gain = 10f32.powf((reduction_db + s.makeup_db) / 20.0);
+    }
+}
+
+/// Runs [`EffectsSettings`] over interleaved samples, one filter state per channel.
+pub struct EffectChain {
+    channels: usize,
+    sample_rate: f64,
+    filters: Vec<Vec<Biquad>>,
+    compressor: Option<Compressor>,
+    gain: f32,
+}
+
+impl EffectChain {
+    pub fn new(settings: &EffectsSettings, channels: u16, sample_rate: u32) -> Self {
+        let mut chain = Self {
+            channels: channels.max(1) as usize,
+            sample_rate: sample_rate.max(1) as f64,
+            filters: Vec::new(),
+            compressor: None,
+            gain: 1.0,
+        };
+        chain.update(settings);
+        chain
+    }
+
+    /// Applies new parameters, keeping filter and detector state so live edits don't click.
+    pub fn update(&mut self, settings: &EffectsSettings) {
+        let rate = self.sample_rate;
+        let mut stages = Vec::new();
+        if let Some(hp) = settings.high_pass {
+            stages.push(design(FilterShape::HighPass, hp.frequency, hp.q, rate));
+        }
+        if let Some(lp) = settings.low_pass {
+            stages.push(design(FilterShape::LowPass, lp.frequency, lp.q, rate));
+        }
+        for band in settings.eq.iter().filter(|b| b.gain_db != 0.0) {
+            stages.push(design(FilterShape::Band(band.kind, band.gain_db as f64), band.frequency, band.q, rate));
+        }
+
+        let previous = std::mem::take(&mut self.filters);
+        self.filters = (0..self.channels)
+            .map(|channel| {
+                stages
+                    .iter()
+                    .enumerate()
+                    .map(|(i, stage)| {
+                        let mut stage = *stage;
+                        if let Some(old) = previous.get(channel).and_then(|f| f.get(i)) {
+                            stage.z1 = old.z1;
+                            stage.z2 = old.z2;
+                        }
+                        stage
+                    })
+                    .collect()
+            })
+            .collect();
+
+        self.compressor = match (settings.compressor, self.compressor.take()) {
+            (Some(s), Some(mut existing)) => {
+                existing.configure(s, self.channels, rate);
+                Some(existing)
+            }
+            (Some(s), None) => Some(Compressor::new(s, self.channels, rate)),
+            (None, _) => None,
+        };
+        self.gain = 10f32.powf(settings.gain_db / 20.0);
+    }
+
+    /// Processes one sample of `channel`; frames are expected in channel order.
+    pub fn process(&mut self, channel: usize, sample: f32) -> f32 {
+        let mut x = sample as f64;
+        if let Some(filters) = self.filters.get_mut(channel) {
+            for filter in filters.iter_mut() {
+                x = filter.process(x);
+            }
+        }
+        let mut y = x as f32;
+
+        if let Some(compressor) = &mut self.compressor {
+            if channel == 0 {
+                compressor.update_gain();
+            }
+            compressor.detect(y);
+            y *= compressor.gain;
+        }
+        y * self.gain
+    }
+}
@@ -1,35 +1,22 @@
 mod audio;
+mod control;
+mod control_server;
+mod devices;
+mod dsp;
+mod loudness;
+mod midi;
+mod osc;
+mod shortcuts;
 
 use audio::AudioState;
 
 use tauri::{Emitter, Listener, Manager};
-use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
 
 #[tauri::command]
 fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
-#[tauri::command]
-async fn register_global_shortcut(app: tauri::AppHandle, shortcut: String, button_id: u32) -> Result<(), String> {
-    let shortcut_obj: Shortcut = shortcut.parse().map_err(|e| format!("Invalid shortcut: {}", e))?;
-    
-    app.global_shortcut().on_shortcut(shortcut_obj.clone(), move |app, _shortcut, event| {
-        if event.state == tauri_plugin_global_shortcut::ShortcutState::Pressed {
-            let _ = app.emit("global-shortcut-triggered", button_id);
-        }
-    }).map_err(|e| format!("Failed to register shortcut: {}", e))?;
-    
-    Ok(())
-}
-
-#[tauri::command]
-async fn unregister_global_shortcut(app: tauri::AppHandle, shortcut: String) -> Result<(), String> {
-    let shortcut_obj: Shortcut = shortcut.parse().map_err(|e| format!("Invalid shortcut: {}", e))?;
-    app.global_shortcut().unregister(shortcut_obj).map_err(|e| format!("Failed to unregister shortcut: {}", e))?;
-    Ok(())
-}
-
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -37,6 +24,11 @@ pub fn run() {
             let handle = app.handle().clone();
             
             app.manage(AudioState::new(handle.clone()));
+            app.manage(control_server::ControlServer::default());
+            app.manage(osc::OscServer::default());
+            app.manage(midi::MidiController::default());
+            app.manage(shortcuts::ShortcutRegistry::default());
+            audio::start_device_watcher(handle.clone());
 
             app.listen_any("tauri://drag-drop", move |event| {
                 if let Ok(payload) = serde_json::from_str::<serde_json::Value>(event.payload()) {
@@ -62,20 +54,48 @@ pub fn run() {
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         .invoke_handler(tauri::generate_handler![
             greet,
+            audio::list_audio_hosts,
+            audio::set_audio_host,
             audio::list_audio_devices,
+            audio::set_output_config,
             audio::set_audio_device,
+            audio::set_monitor_device,
+            audio::set_button_device,
+            audio::set_button_trigger,
             audio::update_master_volume,
+            audio::update_monitor_volume,
+            audio::list_input_devices,
+            audio::set_mic_input,
+            audio::update_mic_gain,
+            audio::set_mic_muted,
+            audio::update_ducking,
             audio::play_sound,
             audio::preload_sound,
+            audio::get_loudness,
+            audio::update_normalization,
+            audio::update_limiter,
+            audio::update_sample_cache,
+            control_server::update_control_server,
+            osc::update_osc_server,
+            midi::list_midi_inputs,
+            midi::list_midi_outputs,
+            midi::update_midi_input,
+            midi::start_midi_learn,
+            midi::cancel_midi_learn,
             audio::toggle_pause_instance,
+            audio::toggle_pause_all,
             audio::stop_instance,
             audio::seek_instance,
+            audio::update_instance_rate,
             audio::stop_all,
             audio::update_button_volume,
+            audio::update_button_effects,
             audio::save_sound_file,
             audio::delete_sound_file,
-            register_global_shortcut,
-            unregister_global_shortcut
+            shortcuts::register_global_shortcut,
+            shortcuts::unregister_global_shortcut,
+            shortcuts::replace_global_shortcuts,
+            shortcuts::list_global_shortcuts
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,288 @@
+use crate::dsp::Biquad;
+use serde::Serialize;
+use std::f64::consts::PI;
+
+/// Loudness figures for one file, following ITU-R BS.1770-4 / EBU R128.
+#[derive(Clone, Copy, Serialize)]
+pub struct LoudnessInfo {
+    pub integrated_lufs: f64,
+    pub loudness_range_lu: f64,
+    pub true_peak_dbtp: f64,
+}
+
+impl LoudnessInfo {
+    /// Gain in dB that brings the clip to `target_lufs` without pushing its
+    /// true peak above `ceiling_dbtp`, boosting by at most `MAX_BOOST_DB`.
+    /// `None` when the whole clip is below the absolute gate (silence or noise).
+    pub fn normalization_gain_db(&self, target_lufs: f64, ceiling_dbtp: f64) -> Option<f64> {
+        if self.integrated_lufs <= ABSOLUTE_GATE_LUFS {
+            return None;
+        }
+        let gain = target_lufs - self.integrated_lufs;
+        Some(gain.min(ceiling_dbtp - self.true_peak_dbtp).min(MAX_BOOST_DB))
+    }
+}
+
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const MAX_BOOST_DB: f64 = 20.0;
+const SILENCE_DB: f64 = -120.0;
+
+/// The two K-weighting stages (high shelf + high pass), derived for any sample rate.
+fn k_weighting(sample_rate: f64) -> [Biquad; 2] {
+    let f0 = 1681.974450955533;
+    let g = 3.999843853973347;
+    let q = 0.7071752369554196;
+    let k = (PI * f0 / sample_rate).tan();
+    let vh = 10f64.powf(g / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b0: (vh + vb * k / q + k * k) / a0,
+        b1: 2.0 * (k * k - vh) / a0,
+        b2: (vh - vb * k / q + k * k) / a0,
+        a1: 2.0 * (k * k - 1.0) / a0,
+        a2: (1.0 - k / q + k * k) / a0,
+        ..Default::default()
+    };
+
+    let f0 = 38.13547087602444;
+    let q = 0.5003270373238773;
+    let k = (PI * f0 / sample_rate).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad {
+        b0: 1.0,
+        b1: -2.0,
+        b2: 1.0,
+        a1: 2.0 * (k * k - 1.0) / a0,
+        a2: (1.0 - k / q + k * k) / a0,
+        ..Default::default()
+    };
+
+    [shelf, high_pass]
+}
+
+/// 4x oversampling peak detector (BS.1770-4 Annex 2), 12 taps per phase.
+struct TruePeakMeter {
+    phases: [[f64; 12]; 4],
+    history: Vec<[f64; 12]>,
+    peak: f64,
+}
+
+impl TruePeakMeter {
+    fn new(channels: usize) -> Self {
+        let mut phases = [[0.0; 12]; 4];
+        let taps = 48;
+        let center = (taps - 1) as f64 / 2.0;
+        for n in 0..taps {
+            let t = (n as f64 - center) / 4.0;
+            let sinc = if t == 0.0 { 1.0 } else { (PI * t).sin() / (PI * t) };
+            let window = 0.5 - 0.5 * (2.0 * PI * n as f64 / (taps - 1) as f64).cos();
+            phases[n % 4][n / 4] = sinc * window;
+        }
+        // Normalize every phase to unity DC gain
+        for phase in phases.iter_mut() {
+            let sum: f64 = phase.iter().sum();
+            phase.iter_mut().for_each(|c| *c /= sum);
+        }
+
+        Self {
+            phases,
+            history: vec![[0.0; 12]; channels],
+            peak: 0.0,
+        }
+    }
+
+    fn process(&mut self, channel: usize, x: f64) {
+        let history = &mut self.history[channel];
+        history.rotate_right(1);
+        history[0] = x;
+
+        self.peak = self.peak.max(x.abs());
+        for phase in &self.phases {
+            let y: f64 = phase.iter().zip(history.iter()).map(|(c, s)| c * s).sum();
+            self.peak = self.peak.max(y.abs());
+        }
+    }
+}
+
+fn energy_to_lufs(energy: f64) -> f64 {
+    if energy <= 0.0 {
+        SILENCE_DB
+    } else {
+        -0.691 + 10.0 * energy.log10()
+    }
+}
+
+fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        0.0
+    } else {
+        values.iter().sum::<f64>() / values.len() as f64
+    }
+}
+
+/// Mean energy of `size`-long windows over 100 ms sub-blocks, hopping one sub-block at a time.
+fn window_energies(sub_blocks: &[f64], size: usize) -> Vec<f64> {
+    if sub_blocks.len() < size {
+        return Vec::new();
+    }
+    sub_blocks
+        .windows(size)
+        .map(|w| w.iter().sum::<f64>() / size as f64)
+        .collect()
+}
+
+fn gated_integrated(block_energies: &[f64]) -> f64 {
+    let above_absolute: Vec<f64> = block_energies
+        .iter()
+        .copied()
+        .filter(|e| energy_to_lufs(*e) > ABSOLUTE_GATE_LUFS)
+        .collect();
+    // Nothing above the gate: report silence, which gets no normalization gain
+    if above_absolute.is_empty() {
+        return SILENCE_DB;
+    }
+    let relative_gate = energy_to_lufs(mean(&above_absolute)) - 10.0;
+    let gated: Vec<f64> = above_absolute
+        .into_iter()
+        .filter(|e| energy_to_lufs(*e) > relative_gate)
+        .collect();
+    energy_to_lufs(mean(&gated))
+}
+
+fn loudness_range(short_term_energies: &[f64]) -> f64 {
+    let above_absolute: Vec<f64> = short_term_energies
+        .iter()
+        .copied()
+        .filter(|e| energy_to_lufs(*e) > ABSOLUTE_GATE_LUFS)
+        .collect();
+    let relative_gate = energy_to_lufs(mean(&above_absolute)) - 20.0;
+    let mut gated: Vec<f64> = above_absolute
+        .into_iter()
+        .map(energy_to_lufs)
+        .filter(|l| *l > relative_gate)
+        .collect();
+    if gated.len() < 2 {
+        return 0.0;
+    }
+    gated.sort_by(|a, b| a.total_cmp(b));
+    let percentile = |p: f64| gated[((gated.len() - 1) as f64 * p).round() as usize];
+    percentile(0.95) - percentile(0.10)
+}
+
+/// Measures interleaved samples. Returns `None` for empty input.
+pub fn analyze<I>(samples: I, channels: u16, sample_rate: u32) -> Option<LoudnessInfo>
+where
+    I: IntoIterator<Item = f32>,
+{
+    let channels = channels.max(1) as usize;
+    let rate = sample_rate.max(1) as f64;
+
+    // LFE is excluded and surrounds are weighted up for 5.1, everything else counts once
+    let weights: Vec<f64> = if channels == 6 {
+        vec![1.0, 1.0, 1.0, 0.0, 1.41, 1.41]
+    } else {
+        vec![1.0; channels]
+    };
+
+    let mut filters = vec![k_weighting(rate); channels];
+    let mut true_peak = TruePeakMeter::new(channels);
+
+    let sub_block_frames = (rate * 0.1).round() as usize;
+    let mut sub_blocks = Vec::new();
+    let mut current_energy = 0.0;
+    let mut current_frames = 0;
+    let mut channel = 0;
+    let mut total_samples = 0usize;
+
+    for sample in samples {
+        let x = sample as f64;
+        true_peak.process(channel, x);
+
+        let [shelf, high_pass] = &mut filters[channel];
+        let y = high_pass.process(shelf.process(x));
+        current_energy += weights[channel] * y * y;
+        total_samples += 1;
+
+        channel += 1;
+        if channel == channels {
+            channel = 0;
+            current_frames += 1;
+            if current_frames == sub_block_frames {
+                sub_blocks.push(current_energy / sub_block_frames as f64);
+                current_energy = 0.0;
+                current_frames = 0;
+            }
+        }
+    }
+
+    if total_samples == 0 {
+        return None;
+    }
+
+    // Clips shorter than one 400 ms block are measured as a single block
+    let integrated_lufs = if sub_blocks.len() >= 4 {
+        gated_integrated(&window_energies(&sub_blocks, 4))
+    } else {
+        let frames = sub_blocks.len() * sub_block_frames + current_frames;
+        let energy = (sub_blocks.iter().sum::<f64>() * sub_block_frames as f64 + current_energy)
+            / frames.max(1) as f64;
+        energy_to_lufs(energy)
+    };
+
+    let loudness_range_lu = loudness_range(&window_energies(&sub_blocks, 30));
+    let true_peak_dbtp = if true_peak.peak > 0.0 {
+        20.0 * true_peak.peak.log10()
+    } else {
+        SILENCE_DB
+    };
+
+    Some(LoudnessInfo {
+        integrated_lufs,
+        loudness_range_lu,
+        true_peak_dbtp,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Mono sine at `amplitude_dbfs` peak.
+    fn sine(freq: f64, amplitude_dbfs: f64, secs: f64, sample_rate: u32) -> Vec<f32> {
+        let amplitude = 10f64.powf(amplitude_dbfs / 20.0);
+        let frames = (secs * sample_rate as f64) as usize;
+        (0..frames)
+            .map(|n| (amplitude * (2.0 * PI * freq * n as f64 / sample_rate as f64).sin()) as f32)
+            .collect()
+    }
+
+    #[test]
+    fn reference_sine_measures_minus_23_lufs() {
+        // BS.1770: a 997 Hz sine on one channel reads 3.01 dB below its peak level
+        let info = analyze(sine(997.0, -20.0, 5.0, 48000), 1, 48000).unwrap();
+        assert!((info.integrated_lufs + 23.0).abs() < 0.1, "{}", info.integrated_lufs);
+        assert!((info.true_peak_dbtp + 20.0).abs() < 0.1, "{}", info.true_peak_dbtp);
+        let gain = info.normalization_gain_db(-16.0, -1.0).unwrap();
+        assert!((gain - 7.0).abs() < 0.1, "{}", gain);
+    }
+
+    #[test]
+    fn silence_is_gated_and_gets_no_gain() {
+        let info = analyze(vec![0.0; 48000 * 2], 2, 48000).unwrap();
+        assert_eq!(info.integrated_lufs, SILENCE_DB);
+        assert!(info.normalization_gain_db(-16.0, -1.0).is_none());
+
+        let quiet = analyze(sine(997.0, -80.0, 2.0, 48000), 1, 48000).unwrap();
+        assert!(quiet.normalization_gain_db(-16.0, -1.0).is_none());
+    }
+
+    #[test]
+    fn input_shorter_than_a_block_is_measured_once() {
+        let info = analyze(sine(997.0, -20.0, 0.2, 48000), 1, 48000).unwrap();
+        assert!((info.integrated_lufs + 23.0).abs() < 0.5, "{}", info.integrated_lufs);
+        assert_eq!(info.loudness_range_lu, 0.0);
+
+        assert!(analyze(Vec::new(), 1, 48000).is_none());
+    }
+}
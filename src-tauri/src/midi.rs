@@ -0,0 +1,409 @@
+//! MIDI input and pad feedback (ALSA sequencer on Linux).
+//!
+//! Settings name an input `port` and/or open a `virtual_port` (a "Claket" input other apps
+//! can connect to, handy for testing). `bindings` map a note-on or CC (`kind`, `channel`,
+//! `number`) to an action: `button`, `master_volume`, `stop_all`, `next_page`,
+//! `previous_page` or `page`. Buttons emit `midi-triggered` (`button_id`, `velocity`) for
+//! the board to play, scaled by velocity with `velocity_volume`; pages emit
+//! `page-change-requested`. A CC at 0 only moves the master volume.
+//!
+//! Learn binds the next note or CC to the given action and emits `midi-learned`.
+//!
+//! `feedback` sends each button's state (`playing_value`, `paused_value`, `idle_value`) to
+//! the notes/CCs bound to it, tracked from `audio-progress` and `audio-finished`. When its
+//! output cannot be opened the input still connects and `midi-feedback-error` is emitted.
+
+use crate::control::{self, ControlCommand};
+use midir::{Ignore, MidiIO, MidiInput, MidiInputConnection, MidiOutput, MidiOutputConnection};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, EventId, Listener, State};
+
+const CLIENT_NAME: &str = "Claket";
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MidiMessageKind {
+    Note,
+    ControlChange,
+}
+
+/// A note or controller on a channel (0-15).
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MidiTrigger {
+    pub kind: MidiMessageKind,
+    pub channel: u8,
+    pub number: u8,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum MidiAction {
+    /// Emits `midi-triggered` so the board plays the button like its global shortcut.
+    Button { button_id: u32 },
+    /// Sets the master volume from the velocity or controller value.
+    MasterVolume,
+    StopAll,
+    NextPage,
+    PreviousPage,
+    Page { page: u32 },
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MidiBinding {
+    pub trigger: MidiTrigger,
+    #[serde(flatten)]
+    pub action: MidiAction,
+}
+
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MidiPortSettings {
+    /// Port name, from `list_midi_inputs` or `list_midi_outputs`.
+    pub port: Option<String>,
+    /// Opens a virtual "Claket" port other applications can connect to (Linux and macOS).
+    pub virtual_port: bool,
+}
+
+/// Lights the controls bound to buttons to show whether they are playing.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MidiFeedbackSettings {
+    #[serde(flatten)]
+    pub ports: MidiPortSettings,
+    /// Velocity or controller value sent for each state; pads use it as the LED colour.
+    pub playing_value: u8,
+    pub paused_value: u8,
+    pub idle_value: u8,
+}
+
+impl Default for MidiFeedbackSettings {
+    fn default() -> Self {
+        Self {
+            ports: MidiPortSettings::default(),
+            playing_value: 127,
+            paused_value: 64,
+            idle_value: 0,
+        }
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MidiSettings {
+    #[serde(flatten)]
+    pub ports: MidiPortSettings,
+    /// Plays buttons at their volume scaled by the note velocity.
+    pub velocity_volume: bool,
+    pub bindings: Vec<MidiBinding>,
+    pub feedback: MidiFeedbackSettings,
+}
+
+/// What the input callback reads, shared so bindings change without reconnecting.
+#[derive(Default)]
+struct Mapping {
+    velocity_volume: bool,
+    bindings: Vec<MidiBinding>,
+    /// While set, the next note-on or controller is bound to this action instead of dispatched.
+    learning: Option<MidiAction>,
+}
+
+/// Note-ons and controller moves, with their 0-127 value. Note-offs and other messages are `None`.
+fn parse_message(bytes: &[u8]) -> Option<(MidiTrigger, u8)> {
+    let [status, number, value] = *bytes else { return None };
+    let channel = status & 0x0F;
+    let kind = match status & 0xF0 {
+        0x90 if value > 0 => MidiMessageKind::Note,
+        0xB0 => MidiMessageKind::ControlChange,
+        _ => return None,
+    };
+    Some((MidiTrigger { kind, channel, number }, value))
+}
+
+/// Port names end with the ALSA `client:port` numbers, which change when a device is replugged.
+fn same_port(a: &str, b: &str) -> bool {
+    let base = |name: &str| -> String {
+        match name.rsplit_once(' ') {
+            Some((base, numbers)) if numbers.split(':').all(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit())) => base.to_string(),
+            _ => name.to_string(),
+        }
+    };
+    a == b || base(a) == base(b)
+}
+
+fn handle_message(app: &AppHandle, mapping: &Mutex<Mapping>, bytes: &[u8]) {
+    let Some((trigger, value)) = parse_message(bytes) else { return };
+    let level = value as f32 / 127.0;
+
+    let action = {
+        let Ok(mut mapping) = mapping.lock() else { return };
+        if let Some(action) = mapping.learning.take() {
+            mapping.bindings.retain(|b| b.trigger != trigger);
+            let binding = MidiBinding { trigger, action };
+            mapping.bindings.push(binding.clone());
+            let _ = app.emit("midi-learned", &binding);
+            return;
+        }
+        let Some(binding) = mapping.bindings.iter().find(|b| b.trigger == trigger) else { return };
+        // A controller at 0 is a pad or button being released
+        if value == 0 && !matches!(binding.action, MidiAction::MasterVolume) {
+            return;
+        }
+        match &binding.action {
+            MidiAction::Button { button_id } => {
+                let velocity = mapping.velocity_volume.then_some(level);
+                let _ = app.emit("midi-triggered", json!({ "button_id": button_id, "velocity": velocity }));
+                return;
+            }
+            action => action.clone(),
+        }
+    };
+
+    let result = match action {
+        MidiAction::MasterVolume => control::execute(app, ControlCommand::SetMasterVolume { volume: level }).map(|_| ()),
+        MidiAction::StopAll => control::execute(app, ControlCommand::StopAll { fade_ms: None }).map(|_| ()),
+        MidiAction::NextPage => app.emit("page-change-requested", "next").map_err(|e| e.to_string()),
+        MidiAction::PreviousPage => app.emit("page-change-requested", "prev").map_err(|e| e.to_string()),
+        MidiAction::Page { page } => app.emit("page-change-requested", page).map_err(|e| e.to_string()),
+        MidiAction::Button { .. } => Ok(()),
+    };
+    if let Err(e) = result {
+        eprintln!("MIDI action failed: {}", e);
+    }
+}
+
+fn new_input() -> Result<MidiInput, String> {
+    let mut input = MidiInput::new(CLIENT_NAME).map_err(|e| format!("Failed to open MIDI: {}", e))?;
+    input.ignore(Ignore::All);
+    Ok(input)
+}
+
+fn new_output() -> Result<MidiOutput, String> {
+    MidiOutput::new(CLIENT_NAME).map_err(|e| format!("Failed to open MIDI: {}", e))
+}
+
+fn port_names<T: MidiIO>(io: &T) -> Vec<String> {
+    io.ports().iter().filter_map(|p| io.port_name(p).ok()).collect()
+}
+
+fn find_port<T: MidiIO>(io: &T, wanted: &str) -> Result<T::Port, String> {
+    io.ports().into_iter()
+        .find(|p| io.port_name(p).is_ok_and(|name| same_port(&name, wanted)))
+        .ok_or(format!("MIDI port not found: {}", wanted))
+}
+
+fn connect(app: &AppHandle, ports: &MidiPortSettings, mapping: &Arc<Mutex<Mapping>>) -> Result<Vec<MidiInputConnection<()>>, String> {
+    let mut connections = Vec::new();
+    let callback = |app: AppHandle, mapping: Arc<Mutex<Mapping>>| {
+        move |_: u64, bytes: &[u8], _: &mut ()| handle_message(&app, &mapping, bytes)
+    };
+
+    if let Some(wanted) = &ports.port {
+        let input = new_input()?;
+        let port = find_port(&input, wanted)?;
+        let connection = input
+            .connect(&port, "Claket Input", callback(app.clone(), Arc::clone(mapping)), ())
+            .map_err(|e| format!("Failed to connect to {}: {}", wanted, e))?;
+        connections.push(connection);
+    }
+
+    #[cfg(unix)]
+    if ports.virtual_port {
+        use midir::os::unix::VirtualInput;
+        let connection = new_input()?
+            .create_virtual(CLIENT_NAME, callback(app.clone(), Arc::clone(mapping)), ())
+            .map_err(|e| format!("Failed to create virtual MIDI input: {}", e))?;
+        connections.push(connection);
+    }
+
+    Ok(connections)
+}
+
+/// Button states sent to the feedback outputs.
+struct Feedback {
+    settings: MidiFeedbackSettings,
+    connections: Vec<MidiOutputConnection>,
+    /// Running instances with their button id and paused flag.
+    instances: HashMap<u32, (String, bool)>,
+}
+
+impl Feedback {
+    fn send_button_state(&mut self, mapping: &Mutex<Mapping>, button_id: &str) {
+        let states: Vec<bool> = self.instances.values()
+            .filter(|(id, _)| id == button_id)
+            .map(|(_, paused)| *paused)
+            .collect();
+        let value = if states.iter().any(|paused| !paused) {
+            self.settings.playing_value
+        } else if !states.is_empty() {
+            self.settings.paused_value
+        } else {
+            self.settings.idle_value
+        };
+
+        let Ok(mapping) = mapping.lock() else { return };
+        let triggers = mapping.bindings.iter().filter_map(|b| match b.action {
+            MidiAction::Button { button_id: id } if id.to_string() == button_id => Some(b.trigger),
+            _ => None,
+        });
+        for trigger in triggers {
+            let status = match trigger.kind {
+                MidiMessageKind::Note => 0x90,
+                MidiMessageKind::ControlChange => 0xB0,
+            } | (trigger.channel & 0x0F);
+            for connection in &mut self.connections {
+                let _ = connection.send(&[status, trigger.number & 0x7F, value & 0x7F]);
+            }
+        }
+    }
+}
+
+struct RunningFeedback {
+    app: AppHandle,
+    listeners: Vec<EventId>,
+}
+
+impl RunningFeedback {
+    fn start(app: &AppHandle, settings: &MidiFeedbackSettings, mapping: &Arc<Mutex<Mapping>>) -> Result<Option<Self>, String> {
+        let mut connections = Vec::new();
+        if let Some(wanted) = &settings.ports.port {
+            let output = new_output()?;
+            let port = find_port(&output, wanted)?;
+            let connection = output
+                .connect(&port, "Claket Feedback")
+                .map_err(|e| format!("Failed to connect to {}: {}", wanted, e))?;
+            connections.push(connection);
+        }
+        #[cfg(unix)]
+        if settings.ports.virtual_port {
+            use midir::os::unix::VirtualOutput;
+            let connection = new_output()?
+                .create_virtual(CLIENT_NAME)
+                .map_err(|e| format!("Failed to create virtual MIDI output: {}", e))?;
+            connections.push(connection);
+        }
+        if connections.is_empty() {
+            return Ok(None);
+        }
+
+        let mut feedback = Feedback { settings: settings.clone(), connections, instances: HashMap::new() };
+        // Start from a dark controller
+        let bound: Vec<String> = mapping.lock().map_err(|_| "Failed to lock MIDI mapping")?.bindings.iter()
+            .filter_map(|b| match b.action {
+                MidiAction::Button { button_id } => Some(button_id.to_string()),
+                _ => None,
+            })
+            .collect();
+        for button_id in bound {
+            feedback.send_button_state(mapping, &button_id);
+        }
+        let feedback = Arc::new(Mutex::new(feedback));
+
+        let listen = |event: &str, handler: fn(&mut Feedback, &Mutex<Mapping>, &Value)| {
+            let feedback = Arc::clone(&feedback);
+            let mapping = Arc::clone(mapping);
+            app.listen_any(event, move |event| {
+                let Ok(payload) = serde_json::from_str::<Value>(event.payload()) else { return };
+                if let Ok(mut feedback) = feedback.lock() {
+                    handler(&mut feedback, &mapping, &payload);
+                }
+            })
+        };
+        let listeners = vec![
+            listen("audio-progress", |feedback, mapping, payload| {
+                let (Some(button_id), Some(instance_id)) = (payload["id"].as_str(), payload["instance_id"].as_u64()) else { return };
+                let state = (button_id.to_string(), payload["is_paused"].as_bool().unwrap_or(false));
+                if feedback.instances.insert(instance_id as u32, state.clone()) != Some(state) {
+                    feedback.send_button_state(mapping, button_id);
+                }
+            }),
+            listen("audio-finished", |feedback, mapping, payload| {
+                let Some(instance_id) = payload.as_u64() else { return };
+                if let Some((button_id, _)) = feedback.instances.remove(&(instance_id as u32)) {
+                    feedback.send_button_state(mapping, &button_id);
+                }
+            }),
+        ];
+
+        Ok(Some(Self { app: app.clone(), listeners }))
+    }
+
+    /// Unlistening drops the last references to the output connections, closing them.
+    fn stop(self) {
+        for id in self.listeners {
+            self.app.unlisten(id);
+        }
+    }
+}
+
+/// MIDI connections and bindings, managed as Tauri state.
+#[derive(Default)]
+pub struct MidiController {
+    mapping: Arc<Mutex<Mapping>>,
+    ports: Mutex<MidiPortSettings>,
+    connections: Mutex<Vec<MidiInputConnection<()>>>,
+    feedback: Mutex<(MidiFeedbackSettings, Option<RunningFeedback>)>,
+}
+
+#[tauri::command]
+pub async fn list_midi_inputs() -> Result<Vec<String>, String> {
+    Ok(port_names(&new_input()?))
+}
+
+#[tauri::command]
+pub async fn list_midi_outputs() -> Result<Vec<String>, String> {
+    Ok(port_names(&new_output()?))
+}
+
+/// Applies MIDI settings, reconnecting only when the ports changed.
+#[tauri::command]
+pub async fn update_midi_input(app: AppHandle, midi: State<'_, MidiController>, settings: MidiSettings) -> Result<(), String> {
+    {
+        let mut mapping = midi.mapping.lock().map_err(|_| "Failed to lock MIDI mapping")?;
+        mapping.velocity_volume = settings.velocity_volume;
+        mapping.bindings = settings.bindings;
+    }
+
+    {
+        let mut ports = midi.ports.lock().map_err(|_| "Failed to lock MIDI ports")?;
+        let mut connections = midi.connections.lock().map_err(|_| "Failed to lock MIDI connections")?;
+        if *ports != settings.ports || connections.is_empty() {
+            // Close before reopening so the virtual port name is free
+            connections.clear();
+            *ports = settings.ports;
+            *connections = connect(&app, &ports, &midi.mapping)?;
+        }
+    }
+
+    // A missing feedback output must not take the input down with it
+    let mut feedback = midi.feedback.lock().map_err(|_| "Failed to lock MIDI feedback")?;
+    if feedback.0 != settings.feedback || feedback.1.is_none() {
+        if let Some(previous) = feedback.1.take() {
+            previous.stop();
+        }
+        feedback.0 = settings.feedback;
+        match RunningFeedback::start(&app, &feedback.0, &midi.mapping) {
+            Ok(running) => feedback.1 = running,
+            Err(e) => {
+                let _ = app.emit("midi-feedback-error", e);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Binds the next note-on or controller move to `action`, replacing what that control did.
+/// The new binding is emitted as `midi-learned`.
+#[tauri::command]
+pub async fn start_midi_learn(midi: State<'_, MidiController>, action: MidiAction) -> Result<(), String> {
+    midi.mapping.lock().map_err(|_| "Failed to lock MIDI mapping")?.learning = Some(action);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn cancel_midi_learn(midi: State<'_, MidiController>) -> Result<(), String> {
+    midi.mapping.lock().map_err(|_| "Failed to lock MIDI mapping")?.learning = None;
+    Ok(())
+}
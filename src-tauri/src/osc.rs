@@ -0,0 +1,297 @@
+//! OSC listener for control surfaces.
+//!
+//! Addresses live under `/claket/`: `button/{id}/play`, `button/{id}/volume`,
+//! `instance/{id}/stop`, `instance/{id}/pause`, `instance/{id}/seek` (ms), `master/volume`
+//! and `stop_all`. Triggers ignore a 0/false argument (button release); stops take an
+//! optional integer fade in ms.
+//!
+//! Senders of `/claket/register [port]` and the configured `clients` receive
+//! `button/{id}/playing`, `button/{id}/paused`, `button/{id}/progress`, `master/level` and
+//! `master/volume`; `/claket/unregister [port]` stops it. Packets from anywhere but this
+//! machine and `allowed_hosts` are dropped, so feedback cannot be pointed at a spoofed address.
+
+use crate::audio::AudioState;
+use crate::control::{self, ControlCommand};
+use rosc::{OscMessage, OscPacket, OscType};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tauri::{AppHandle, EventId, Listener, Manager, State};
+
+const ADDRESS_PREFIX: &str = "/claket/";
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OscSettings {
+    pub enabled: bool,
+    /// Local address to listen on; only this machine can reach the default.
+    pub bind_address: String,
+    pub port: u16,
+    /// Source IPs allowed to send commands and register, besides this machine.
+    pub allowed_hosts: Vec<String>,
+    /// Receive feedback without sending `/claket/register`, as `host:port`.
+    pub clients: Vec<String>,
+}
+
+impl Default for OscSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: "127.0.0.1".to_string(),
+            port: 9000,
+            allowed_hosts: Vec::new(),
+            clients: Vec::new(),
+        }
+    }
+}
+
+/// What feedback clients are told about.
+#[derive(Default)]
+struct Feedback {
+    clients: HashSet<SocketAddr>,
+    /// Running instances with their button id and paused flag.
+    instances: HashMap<u32, (String, bool)>,
+}
+
+impl Feedback {
+    fn send(&self, socket: &UdpSocket, addr: String, args: Vec<OscType>) {
+        let packet = OscPacket::Message(OscMessage { addr, args });
+        if let Ok(bytes) = rosc::encoder::encode(&packet) {
+            for client in &self.clients {
+                let _ = socket.send_to(&bytes, client);
+            }
+        }
+    }
+
+    /// Sends `playing` and `paused` (1.0 / 0.0) for a button, from all of its instances.
+    fn send_button_state(&self, socket: &UdpSocket, button_id: &str) {
+        let states: Vec<bool> = self.instances.values()
+            .filter(|(id, _)| id == button_id)
+            .map(|(_, paused)| *paused)
+            .collect();
+        let playing = states.iter().any(|paused| !paused);
+        let paused = !states.is_empty() && !playing;
+        let flag = |on: bool| vec![OscType::Float(if on { 1.0 } else { 0.0 })];
+        self.send(socket, format!("{}button/{}/playing", ADDRESS_PREFIX, button_id), flag(playing));
+        self.send(socket, format!("{}button/{}/paused", ADDRESS_PREFIX, button_id), flag(paused));
+    }
+}
+
+enum Request {
+    Command(ControlCommand),
+    /// Feedback goes to the sender's address, on the given port if any.
+    Register(Option<u16>),
+    Unregister(Option<u16>),
+}
+
+fn number(arg: &OscType) -> Option<f64> {
+    match arg {
+        OscType::Int(v) => Some(*v as f64),
+        OscType::Long(v) => Some(*v as f64),
+        OscType::Float(v) => Some(*v as f64),
+        OscType::Double(v) => Some(*v),
+        OscType::Bool(v) => Some(if *v { 1.0 } else { 0.0 }),
+        _ => None,
+    }
+}
+
+/// Buttons on control surfaces send 1 on press and 0 on release; releases are ignored.
+fn is_release(args: &[OscType]) -> bool {
+    matches!(
+        args.first(),
+        Some(OscType::Int(0)) | Some(OscType::Long(0)) | Some(OscType::Bool(false))
+    ) || matches!(args.first(), Some(OscType::Float(v)) if *v == 0.0)
+}
+
+/// Fades are given as an integer number of milliseconds.
+fn fade_arg(args: &[OscType]) -> Option<u64> {
+    args.iter().find_map(|arg| match arg {
+        OscType::Int(v) => u64::try_from(*v).ok(),
+        OscType::Long(v) => u64::try_from(*v).ok(),
+        _ => None,
+    })
+}
+
+fn parse_request(message: &OscMessage) -> Option<Request> {
+    let path = message.addr.strip_prefix(ADDRESS_PREFIX)?;
+    let segments: Vec<&str> = path.split('/').collect();
+    let args = message.args.as_slice();
+    let value = args.first().and_then(number);
+
+    let command = match segments.as_slice() {
+        ["register"] => return Some(Request::Register(value.map(|port| port as u16))),
+        ["unregister"] => return Some(Request::Unregister(value.map(|port| port as u16))),
+        ["button", id, "volume"] => ControlCommand::SetButtonVolume { button_id: id.to_string(), volume: value? as f32 },
+        ["instance", id, "seek"] => ControlCommand::Seek { instance_id: id.parse().ok()?, position_ms: value? as u64 },
+        ["master", "volume"] => ControlCommand::SetMasterVolume { volume: value? as f32 },
+        // Everything below is a trigger
+        _ if is_release(args) => return None,
+        ["button", id, "play"] => ControlCommand::TriggerButton { button_id: id.parse().ok()? },
+        ["instance", id, "stop"] => ControlCommand::Stop { instance_id: id.parse().ok()?, fade_ms: fade_arg(args) },
+        ["instance", id, "pause"] => ControlCommand::TogglePause { instance_id: id.parse().ok()? },
+        ["stop_all"] => ControlCommand::StopAll { fade_ms: fade_arg(args) },
+        _ => return None,
+    };
+    Some(Request::Command(command))
+}
+
+fn flatten(packet: OscPacket, messages: &mut Vec<OscMessage>) {
+    match packet {
+        OscPacket::Message(message) => messages.push(message),
+        OscPacket::Bundle(bundle) => bundle.content.into_iter().for_each(|p| flatten(p, messages)),
+    }
+}
+
+struct RunningOsc {
+    app: AppHandle,
+    stop: Arc<AtomicBool>,
+    listeners: Vec<EventId>,
+    thread: JoinHandle<()>,
+}
+
+impl RunningOsc {
+    fn start(app: &AppHandle, settings: &OscSettings) -> Result<Self, String> {
+        let bind_address: IpAddr = settings.bind_address.trim().parse()
+            .map_err(|_| format!("Invalid OSC bind address: {}", settings.bind_address))?;
+        let allowed_hosts = settings.allowed_hosts.iter()
+            .map(|host| host.trim().parse::<IpAddr>().map_err(|_| format!("Invalid OSC allowed host: {}", host)))
+            .collect::<Result<HashSet<IpAddr>, String>>()?;
+        let socket = UdpSocket::bind((bind_address, settings.port))
+            .map_err(|e| format!("Failed to listen on UDP port {}: {}", settings.port, e))?;
+        socket.set_read_timeout(Some(POLL_INTERVAL)).map_err(|e| e.to_string())?;
+        let socket = Arc::new(socket);
+
+        let mut feedback = Feedback::default();
+        for client in &settings.clients {
+            let addr = client.to_socket_addrs().ok().and_then(|mut a| a.next())
+                .ok_or(format!("Invalid OSC client address: {}", client))?;
+            feedback.clients.insert(addr);
+        }
+        let feedback = Arc::new(Mutex::new(feedback));
+
+        let listeners = vec![
+            Self::listen(app, &socket, &feedback, "audio-progress", |feedback, socket, payload| {
+                let (Some(button_id), Some(instance_id)) = (payload["id"].as_str(), payload["instance_id"].as_u64()) else { return };
+                let paused = payload["is_paused"].as_bool().unwrap_or(false);
+                let state = (button_id.to_string(), paused);
+                if feedback.instances.insert(instance_id as u32, state.clone()) != Some(state) {
+                    feedback.send_button_state(socket, button_id);
+                }
+
+                let duration = payload["duration_ms"].as_f64().unwrap_or(0.0);
+                if duration > 0.0 {
+                    let progress = payload["position_ms"].as_f64().unwrap_or(0.0) / duration;
+                    feedback.send(socket, format!("{}button/{}/progress", ADDRESS_PREFIX, button_id), vec![OscType::Float(progress as f32)]);
+                }
+            }),
+            Self::listen(app, &socket, &feedback, "audio-finished", |feedback, socket, payload| {
+                let Some(instance_id) = payload.as_u64() else { return };
+                if let Some((button_id, _)) = feedback.instances.remove(&(instance_id as u32)) {
+                    feedback.send_button_state(socket, &button_id);
+                }
+            }),
+            Self::listen(app, &socket, &feedback, "master-level", |feedback, socket, payload| {
+                let level = |key: &str| OscType::Float(payload[key].as_f64().unwrap_or(0.0) as f32);
+                feedback.send(socket, format!("{}master/level", ADDRESS_PREFIX), vec![level("peak"), level("rms")]);
+            }),
+            Self::listen(app, &socket, &feedback, "master-volume-changed", |feedback, socket, payload| {
+                let volume = payload.as_f64().unwrap_or(0.0) as f32;
+                feedback.send(socket, format!("{}master/volume", ADDRESS_PREFIX), vec![OscType::Float(volume)]);
+            }),
+        ];
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_clone = Arc::clone(&stop);
+        let app_clone = app.clone();
+        let thread = std::thread::spawn(move || {
+            let mut buffer = [0u8; rosc::decoder::MTU];
+            while !stop_clone.load(Ordering::Relaxed) {
+                let (size, sender) = match socket.recv_from(&mut buffer) {
+                    Ok(received) => received,
+                    Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => continue,
+                    Err(_) => continue,
+                };
+                // Anyone else could drive playback, or register a spoofed address for feedback
+                if !sender.ip().is_loopback() && !allowed_hosts.contains(&sender.ip()) {
+                    continue;
+                }
+                let Ok((_, packet)) = rosc::decoder::decode_udp(&buffer[..size]) else { continue };
+
+                let mut messages = Vec::new();
+                flatten(packet, &mut messages);
+                for message in messages {
+                    match parse_request(&message) {
+                        Some(Request::Command(command)) => {
+                            if let Err(e) = control::execute(&app_clone, command) {
+                                eprintln!("OSC {} failed: {}", message.addr, e);
+                            }
+                        }
+                        Some(Request::Register(port)) => {
+                            let client = SocketAddr::new(sender.ip(), port.unwrap_or(sender.port()));
+                            let mut feedback = feedback.lock().unwrap();
+                            feedback.clients.insert(client);
+                            // Bring the new client's faders up to date
+                            let volume = *app_clone.state::<AudioState>().master_volume.lock().unwrap();
+                            feedback.send(&socket, format!("{}master/volume", ADDRESS_PREFIX), vec![OscType::Float(volume)]);
+                        }
+                        Some(Request::Unregister(port)) => {
+                            let client = SocketAddr::new(sender.ip(), port.unwrap_or(sender.port()));
+                            feedback.lock().unwrap().clients.remove(&client);
+                        }
+                        None => {}
+                    }
+                }
+            }
+        });
+
+        Ok(Self { app: app.clone(), stop, listeners, thread })
+    }
+
+    fn listen<F>(app: &AppHandle, socket: &Arc<UdpSocket>, feedback: &Arc<Mutex<Feedback>>, event: &str, handler: F) -> EventId
+    where
+        F: Fn(&mut Feedback, &UdpSocket, &Value) + Send + 'static,
+    {
+        let socket = Arc::clone(socket);
+        let feedback = Arc::clone(feedback);
+        app.listen_any(event, move |event| {
+            let Ok(payload) = serde_json::from_str::<Value>(event.payload()) else { return };
+            if let Ok(mut feedback) = feedback.lock() {
+                handler(&mut feedback, &socket, &payload);
+            }
+        })
+    }
+
+    fn stop(self) {
+        self.stop.store(true, Ordering::Relaxed);
+        for id in self.listeners {
+            self.app.unlisten(id);
+        }
+        let _ = self.thread.join();
+    }
+}
+
+/// OSC listener for control surfaces, managed as Tauri state.
+#[derive(Default)]
+pub struct OscServer {
+    running: Mutex<Option<RunningOsc>>,
+}
+
+/// Starts, restarts or stops the OSC listener.
+#[tauri::command]
+pub async fn update_osc_server(app: AppHandle, server: State<'_, OscServer>, settings: OscSettings) -> Result<(), String> {
+    let mut running = server.running.lock().map_err(|_| "Failed to lock OSC server")?;
+    if let Some(previous) = running.take() {
+        previous.stop();
+    }
+    if settings.enabled {
+        *running = Some(RunningOsc::start(&app, &settings)?);
+    }
+    Ok(())
+}
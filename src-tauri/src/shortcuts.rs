@@ -0,0 +1,345 @@
+//! Global shortcuts.
+//!
+//! An accelerator runs an `action`: `play_button` (`button_id`, `mode`, `fade_ms`),
+//! `stop_all` (`fade_ms`), `toggle_pause_all`, `master_volume_step` (`step`), `toggle_mute`,
+//! `next_page`, `previous_page` or `switch_output_device` (`device_id`, or the next listed
+//! output when omitted). Button presses emit `global-shortcut-triggered` for the board to
+//! play and page actions emit `page-change-requested`. Hold releases and toggle stops fade
+//! over `fade_ms`; a hold release that beats the play it triggered cancels it.
+//!
+//! The `ShortcutRegistry` maps each accelerator to its `shortcut` text and flattened action.
+//! Registering an accelerator that already does something else fails with what it is
+//! assigned to. Replacing checks the whole list first (invalid or duplicated accelerators
+//! fail the call without touching the current bindings), then returns the shortcuts the
+//! OS refused.
+
+use crate::audio::{self, AudioState};
+use crate::control::{self, ControlCommand};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::mem::discriminant;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+/// How a global shortcut drives its button.
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerMode {
+    /// Plays on every press.
+    #[default]
+    Press,
+    /// Plays while the keys are held and stops on release.
+    Hold,
+    /// Plays on a press, stops on the next one.
+    Toggle,
+}
+
+/// What a global shortcut does. Everything but playing a button and changing pages
+/// runs in Rust, so it works while the window is hidden.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ShortcutAction {
+    /// Emits `global-shortcut-triggered` for the board, which knows the button's sound.
+    PlayButton {
+        button_id: u32,
+        #[serde(default)]
+        mode: TriggerMode,
+        /// Fades the stops of hold and toggle modes.
+        #[serde(default)]
+        fade_ms: u64,
+    },
+    StopAll {
+        #[serde(default)]
+        fade_ms: u64,
+    },
+    TogglePauseAll,
+    /// Adds `step` (negative to lower) to the master volume.
+    MasterVolumeStep { step: f32 },
+    ToggleMute,
+    NextPage,
+    PreviousPage,
+    /// Selects `device_id`, or the next listed output when `None`.
+    SwitchOutputDevice {
+        #[serde(default)]
+        device_id: Option<String>,
+    },
+}
+
+impl ShortcutAction {
+    /// Whether two actions control the same thing, so one can replace the other.
+    fn same_target(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::PlayButton { button_id: a, .. }, Self::PlayButton { button_id: b, .. }) => a == b,
+            (Self::SwitchOutputDevice { device_id: a }, Self::SwitchOutputDevice { device_id: b }) => a == b,
+            _ => discriminant(self) == discriminant(other),
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Self::PlayButton { button_id, .. } => format!("button {}", button_id + 1),
+            Self::StopAll { .. } => "stop all".to_string(),
+            Self::TogglePauseAll => "pause/resume all".to_string(),
+            Self::MasterVolumeStep { step } if *step < 0.0 => "volume down".to_string(),
+            Self::MasterVolumeStep { .. } => "volume up".to_string(),
+            Self::ToggleMute => "mute".to_string(),
+            Self::NextPage => "next page".to_string(),
+            Self::PreviousPage => "previous page".to_string(),
+            Self::SwitchOutputDevice { device_id: Some(id) } => format!("output {}", id),
+            Self::SwitchOutputDevice { device_id: None } => "next output".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ShortcutBinding {
+    /// The accelerator as the user entered it, e.g. `Ctrl+Shift+1`.
+    pub shortcut: String,
+    #[serde(flatten)]
+    pub action: ShortcutAction,
+}
+
+/// A binding the OS refused during `replace_global_shortcuts`.
+#[derive(Serialize)]
+pub struct ShortcutFailure {
+    pub shortcut: String,
+    pub error: String,
+}
+
+/// Registered global shortcuts, managed as Tauri state. Keyed by the parsed accelerator,
+/// so `Ctrl+1` and `Control+1` are the same shortcut.
+#[derive(Default)]
+pub struct ShortcutRegistry {
+    bindings: Mutex<HashMap<Shortcut, ShortcutBinding>>,
+    /// Master volume before `ToggleMute` muted it.
+    unmuted_volume: Mutex<Option<f32>>,
+}
+
+fn parse(shortcut: &str) -> Result<Shortcut, String> {
+    shortcut.parse().map_err(|e| format!("Invalid shortcut {}: {}", shortcut, e))
+}
+
+fn play_button(app: &AppHandle, button_id: u32, mode: TriggerMode, fade_ms: u64, state: ShortcutState) {
+    let audio = app.state::<AudioState>();
+    let id = button_id.to_string();
+    match (mode, state) {
+        (TriggerMode::Press, ShortcutState::Pressed) => {
+            let _ = app.emit("global-shortcut-triggered", button_id);
+        }
+        (TriggerMode::Hold, ShortcutState::Pressed) => {
+            audio.press_button(&id);
+            let _ = app.emit("global-shortcut-triggered", button_id);
+        }
+        (TriggerMode::Hold, ShortcutState::Released) => audio.release_button(&id, fade_ms),
+        (TriggerMode::Toggle, ShortcutState::Pressed) => {
+            if !audio.stop_button(&id, fade_ms) {
+                let _ = app.emit("global-shortcut-triggered", button_id);
+            }
+        }
+        (TriggerMode::Press | TriggerMode::Toggle, ShortcutState::Released) => {}
+    }
+}
+
+fn set_master_volume(app: &AppHandle, volume: f32) -> Result<(), String> {
+    control::execute(app, ControlCommand::SetMasterVolume { volume }).map(|_| ())
+}
+
+fn toggle_mute(app: &AppHandle) -> Result<(), String> {
+    let registry = app.state::<ShortcutRegistry>();
+    let mut unmuted = registry.unmuted_volume.lock().map_err(|_| "Failed to lock mute state")?;
+    let current = *app.state::<AudioState>().master_volume.lock().map_err(|_| "Failed to lock master volume")?;
+    match unmuted.take() {
+        // Still muted since the last press
+        Some(volume) if current == 0.0 => set_master_volume(app, volume),
+        _ => {
+            *unmuted = Some(current);
+            set_master_volume(app, 0.0)
+        }
+    }
+}
+
+fn switch_output_device(app: &AppHandle, device_id: Option<String>) -> Result<(), String> {
+    let device_id = match device_id {
+        Some(id) => id,
+        None => {
+            let state = app.state::<AudioState>();
+            let current = state.current_device_id.lock().map_err(|_| "Failed to lock current device")?.clone();
+            let devices = tauri::async_runtime::block_on(audio::list_audio_devices(state))?;
+            let index = devices.iter().position(|d| d.id == current).map_or(0, |i| (i + 1) % devices.len());
+            devices.get(index).ok_or("No audio devices")?.id.clone()
+        }
+    };
+    control::execute(app, ControlCommand::SetDevice { device_id }).map(|_| ())
+}
+
+/// Runs on the event loop thread, so actions work while the window is hidden. Button
+/// presses and releases only touch in-memory state and stay in order here; everything
+/// else may open devices and runs on its own thread.
+fn on_shortcut(app: &AppHandle, shortcut: &Shortcut, state: ShortcutState) {
+    let action = {
+        let registry = app.state::<ShortcutRegistry>();
+        let Ok(bindings) = registry.bindings.lock() else { return };
+        let Some(binding) = bindings.get(shortcut) else { return };
+        binding.action.clone()
+    };
+    if let ShortcutAction::PlayButton { button_id, mode, fade_ms } = action {
+        return play_button(app, button_id, mode, fade_ms, state);
+    }
+    if state != ShortcutState::Pressed {
+        return;
+    }
+
+    let app = app.clone();
+    let shortcut = shortcut.to_string();
+    std::thread::spawn(move || {
+        if let Err(e) = run_action(&app, action) {
+            eprintln!("Shortcut {} failed: {}", shortcut, e);
+        }
+    });
+}
+
+fn run_action(app: &AppHandle, action: ShortcutAction) -> Result<(), String> {
+    match action {
+        ShortcutAction::StopAll { fade_ms } => control::execute(app, ControlCommand::StopAll { fade_ms: Some(fade_ms) }).map(|_| ()),
+        ShortcutAction::TogglePauseAll => {
+            tauri::async_runtime::block_on(audio::toggle_pause_all(app.state::<AudioState>())).map(|_| ())
+        }
+        ShortcutAction::MasterVolumeStep { step } => {
+            let volume = *app.state::<AudioState>().master_volume.lock().unwrap();
+            set_master_volume(app, volume + step)
+        }
+        ShortcutAction::ToggleMute => toggle_mute(app),
+        ShortcutAction::NextPage => app.emit("page-change-requested", "next").map_err(|e| e.to_string()),
+        ShortcutAction::PreviousPage => app.emit("page-change-requested", "prev").map_err(|e| e.to_string()),
+        ShortcutAction::SwitchOutputDevice { device_id } => switch_output_device(app, device_id),
+        ShortcutAction::PlayButton { .. } => Ok(()),
+    }
+}
+
+/// Registers with the OS. Must not be called with the registry locked: shortcut events
+/// look bindings up on the thread the registration may wait for.
+fn register_os(app: &AppHandle, shortcut: Shortcut, text: &str) -> Result<(), String> {
+    app.global_shortcut()
+        .on_shortcut(shortcut, |app, shortcut, event| on_shortcut(app, shortcut, event.state))
+        .map_err(|e| format!("Failed to register {} (it may be taken by another application): {}", text, e))
+}
+
+/// Binds a shortcut to an action, or updates the options of its existing binding to the
+/// same target (e.g. the mode of a button). Fails if the shortcut does something else.
+#[tauri::command]
+pub async fn register_global_shortcut(
+    app: AppHandle,
+    registry: State<'_, ShortcutRegistry>,
+    shortcut: String,
+    action: ShortcutAction,
+) -> Result<(), String> {
+    let parsed = parse(&shortcut)?;
+    let binding = ShortcutBinding { shortcut, action };
+
+    let already_registered = {
+        let mut bindings = registry.bindings.lock().map_err(|_| "Failed to lock shortcuts")?;
+        match bindings.get(&parsed) {
+            Some(existing) if !existing.action.same_target(&binding.action) => {
+                return Err(format!("{} is already assigned to {}", existing.shortcut, existing.action.describe()));
+            }
+            Some(_) => {
+                bindings.insert(parsed, binding.clone());
+                true
+            }
+            None => false,
+        }
+    };
+    if already_registered {
+        return Ok(());
+    }
+
+    register_os(&app, parsed, &binding.shortcut)?;
+    registry.bindings.lock().map_err(|_| "Failed to lock shortcuts")?.insert(parsed, binding);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn unregister_global_shortcut(app: AppHandle, registry: State<'_, ShortcutRegistry>, shortcut: String) -> Result<(), String> {
+    let parsed = parse(&shortcut)?;
+    registry.bindings.lock().map_err(|_| "Failed to lock shortcuts")?.remove(&parsed);
+    app.global_shortcut().unregister(parsed).map_err(|e| format!("Failed to unregister shortcut: {}", e))?;
+    Ok(())
+}
+
+/// Replaces every binding, e.g. when a board is loaded. The list is checked as a whole
+/// first: an invalid or duplicated shortcut fails the call and keeps the current bindings.
+/// If the OS then refuses any shortcut, the previous bindings are restored and the refused
+/// shortcuts are returned.
+#[tauri::command]
+pub async fn replace_global_shortcuts(
+    app: AppHandle,
+    registry: State<'_, ShortcutRegistry>,
+    bindings: Vec<ShortcutBinding>,
+) -> Result<Vec<ShortcutFailure>, String> {
+    let mut parsed: HashMap<Shortcut, ShortcutBinding> = HashMap::new();
+    let mut errors = Vec::new();
+    for binding in bindings {
+        match parse(&binding.shortcut) {
+            Ok(shortcut) => {
+                if let Some(other) = parsed.get(&shortcut) {
+                    errors.push(format!(
+                        "{} is assigned to both {} and {}",
+                        binding.shortcut, other.action.describe(), binding.action.describe()
+                    ));
+                } else {
+                    parsed.insert(shortcut, binding);
+                }
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors.join("\n"));
+    }
+
+    // The registry is only cleared once the OS has let go, so it never forgets a live hotkey
+    let previous: HashMap<Shortcut, ShortcutBinding> = registry.bindings.lock().map_err(|_| "Failed to lock shortcuts")?.clone();
+    if !previous.is_empty() {
+        app.global_shortcut()
+            .unregister_multiple(previous.keys().cloned().collect::<Vec<_>>())
+            .map_err(|e| format!("Failed to unregister shortcuts: {}", e))?;
+    }
+    registry.bindings.lock().map_err(|_| "Failed to lock shortcuts")?.clear();
+
+    let mut registered = Vec::new();
+    let mut failures = Vec::new();
+    for (shortcut, binding) in &parsed {
+        match register_os(&app, *shortcut, &binding.shortcut) {
+            Ok(()) => registered.push(*shortcut),
+            Err(error) => failures.push(ShortcutFailure { shortcut: binding.shortcut.clone(), error }),
+        }
+    }
+
+    let bindings = if failures.is_empty() {
+        parsed
+    } else {
+        // Roll back to the previous set; keep whatever of it the OS takes back
+        let _ = app.global_shortcut().unregister_multiple(registered);
+        previous
+            .into_iter()
+            .filter(|(shortcut, binding)| match register_os(&app, *shortcut, &binding.shortcut) {
+                Ok(()) => true,
+                Err(e) => {
+                    eprintln!("Failed to restore shortcut: {}", e);
+                    false
+                }
+            })
+            .collect()
+    };
+    *registry.bindings.lock().map_err(|_| "Failed to lock shortcuts")? = bindings;
+    Ok(failures)
+}
+
+/// Current bindings, ordered by accelerator.
+#[tauri::command]
+pub async fn list_global_shortcuts(registry: State<'_, ShortcutRegistry>) -> Result<Vec<ShortcutBinding>, String> {
+    let mut bindings: Vec<ShortcutBinding> = registry.bindings.lock().map_err(|_| "Failed to lock shortcuts")?.values().cloned().collect();
+    bindings.sort_by(|a, b| a.shortcut.cmp(&b.shortcut));
+    Ok(bindings)
+}
@@ -4,6 +4,20 @@ import { Store } from "@tauri-apps/plugin-store";
 import { listen } from "@tauri-apps/api/event";
 import { toast } from "vue-sonner";
 
+export type ShortcutMode = "press" | "hold" | "toggle";
+
+// What pressing a button does while it is already playing
+export type TriggerPolicy = "polyphonic" | "restart" | "toggle_stop" | "ignore_while_playing" | "queue";
+
+// Global shortcut actions other than playing a button
+export type ShortcutAction =
+  | { action: "stop_all", fade_ms?: number }
+  | { action: "toggle_pause_all" | "toggle_mute" | "next_page" | "previous_page" }
+  | { action: "master_volume_step", step: number }
+  | { action: "switch_output_device", device_id?: string | null };
+
+export type ActionShortcut = ShortcutAction & { shortcut: string };
+
 export interface SoundButton {
   id: number;
   path: string | null;
@@ -13,6 +27,29 @@ export interface SoundButton {
   isPaused: boolean;
   activeInstances: number;
   shortcut: string | null;
+  shortcutMode: ShortcutMode;
+  // Fade applied when a hold or toggle shortcut stops the sound
+  shortcutFadeMs: number;
+  trigger: TriggerPolicy;
+  // Starting the button stops every other button in the same group
+  chokeGroup: string | null;
+  // Output device id that replaces the main and monitor outputs for this button
+  device: string | null;
+}
+
+export interface DeviceInfo {
+  id: string;
+  host: string;
+  name: string;
+  is_default: boolean;
+  sample_rates: number[];
+  channels: number[];
+}
+
+// Device picked for a requested id; `suggested` marks a similar-named stand-in
+export interface DeviceSelection {
+  id: string;
+  suggested: boolean;
 }
 
 export interface AudioProgress {
@@ -22,10 +59,98 @@ export interface AudioProgress {
   position_ms: number;
   duration_ms: number;
   is_paused: boolean;
+  tempo: number;
   last_sync_time: number;
   last_sync_pos: number;
 }
 
+export type MidiAction =
+  | { action: "button", button_id: number }
+  | { action: "master_volume" | "stop_all" | "next_page" | "previous_page" }
+  | { action: "page", page: number };
+
+export type MidiBinding = MidiAction & {
+  trigger: { kind: "note" | "control_change", channel: number, number: number };
+};
+
+export interface MidiSettings {
+  port: string | null;
+  virtual_port: boolean;
+  velocity_volume: boolean;
+  bindings: MidiBinding[];
+  feedback: {
+    port: string | null;
+    virtual_port: boolean;
+    playing_value: number;
+    paused_value: number;
+    idle_value: number;
+  };
+}
+
+export interface OutputConfig {
+  // null keeps the device default
+  sample_rate: number | null;
+  // Frames per callback
+  buffer_size: number | null;
+}
+
+export interface MicSettings {
+  // Input device name, null when passthrough is off
+  device: string | null;
+  gain: number;
+  muted: boolean;
+}
+
+export interface MonitorSettings {
+  // Output device id for the monitor copy, null when monitoring is off
+  device: string | null;
+  volume: number;
+}
+
+export interface SampleCacheSettings {
+  enabled: boolean;
+  budget_mb: number;
+  // Longer clips keep streaming from disk
+  max_clip_secs: number;
+}
+
+export interface NormalizationSettings {
+  // Plays every clip at the target loudness measured by preload_sound
+  enabled: boolean;
+  target_lufs: number;
+  ceiling_dbtp: number;
+}
+
+export interface DuckingSettings {
+  enabled: boolean;
+  amount_db: number;
+  attack_ms: number;
+  release_ms: number;
+  duck_mic: boolean;
+}
+
+export interface LimiterSettings {
+  enabled: boolean;
+  ceiling_db: number;
+  release_ms: number;
+}
+
+export interface ControlServerSettings {
+  enabled: boolean;
+  port: number;
+  // Generated by the backend on first start
+  token: string;
+}
+
+export interface OscSettings {
+  enabled: boolean;
+  bind_address: string;
+  port: number;
+  // Source IPs allowed to send commands besides this machine
+  allowed_hosts: string[];
+  clients: string[];
+}
+
 export const ACCENT_COLORS: Record<string, { light: string, dark: string }> = {
   'Default': { light: 'oklch(0.21 0.006 285.885)', dark: 'oklch(0.985 0 0)' },
   'Indigo': { light: 'oklch(0.55 0.22 260)', dark: 'oklch(0.65 0.18 265)' },
@@ -45,8 +170,11 @@ export const useAudioStore = defineStore("audio", {
   state: () => ({
     buttons: [] as SoundButton[],
     masterVolume: 1.0,
-    devices: [] as string[],
+    devices: [] as DeviceInfo[],
     currentDevice: "Default",
+    // Audio backend name, null for the platform default
+    audioHost: null as string | null,
+    outputConfigs: {} as Record<string, OutputConfig>,
     isLoaded: false,
     activeProgresses: new Map<number, AudioProgress>(),
     isQueueExpanded: false,
@@ -61,11 +189,28 @@ export const useAudioStore = defineStore("audio", {
     lastUpdateTimestamp: new Map<number, number>(),
     reducedMotion: true,
     accentColor: "Default",
-    masterLevels: { peak: 0, rms: 0 },
+    masterLevels: { peak: 0, rms: 0, gain_reduction_db: 0 },
     isVUMeterExpanded: false,
     titlebarStyle: "windows" as "windows" | "mac",
     controlsSide: "right" as "left" | "right",
     isDarkMode: false,
+    midi: {
+      port: null,
+      virtual_port: false,
+      velocity_volume: false,
+      bindings: [],
+      feedback: { port: null, virtual_port: false, playing_value: 127, paused_value: 64, idle_value: 0 },
+    } as MidiSettings,
+    inputDevices: [] as string[],
+    mic: { device: null, gain: 1, muted: false } as MicSettings,
+    monitor: { device: null, volume: 1 } as MonitorSettings,
+    sampleCache: { enabled: false, budget_mb: 256, max_clip_secs: 30 } as SampleCacheSettings,
+    normalization: { enabled: false, target_lufs: -16, ceiling_dbtp: -1 } as NormalizationSettings,
+    ducking: { enabled: false, amount_db: 12, attack_ms: 50, release_ms: 400, duck_mic: true } as DuckingSettings,
+    limiter: { enabled: true, ceiling_db: -1, release_ms: 150 } as LimiterSettings,
+    controlServer: { enabled: false, port: 7373, token: "" } as ControlServerSettings,
+    osc: { enabled: false, bind_address: "127.0.0.1", port: 9000, allowed_hosts: [], clients: [] } as OscSettings,
+    actionShortcuts: [] as ActionShortcut[],
   }),
   getters: {
     queueList: (state) => Array.from(state.activeProgresses.values()).reverse(),
@@ -98,6 +243,11 @@ export const useAudioStore = defineStore("audio", {
             isPaused: false,
             activeInstances: 0,
             shortcut: null,
+            shortcutMode: "press",
+            shortcutFadeMs: 0,
+            trigger: "polyphonic",
+            chokeGroup: null,
+            device: null,
           });
         }
       }
@@ -127,7 +277,7 @@ export const useAudioStore = defineStore("audio", {
 
           if (!progress.is_paused) {
              const elapsedSinceSync = now - progress.last_sync_time;
-             const projected = progress.last_sync_pos + elapsedSinceSync;
+             const projected = progress.last_sync_pos + elapsedSinceSync * progress.tempo;
              progress.position_ms = Math.min(progress.duration_ms, projected);
           }
         });
@@ -137,7 +287,7 @@ export const useAudioStore = defineStore("audio", {
     },
 
     setupListeners() {
-      listen<{ peak: number, rms: number }>("master-level", (event) => {
+      listen<{ peak: number, rms: number, gain_reduction_db: number }>("master-level", (event) => {
         this.masterLevels = event.payload;
       });
 
@@ -163,11 +313,12 @@ export const useAudioStore = defineStore("audio", {
             existing.last_sync_time = now;
             existing.is_paused = payload.is_paused;
             existing.duration_ms = payload.duration_ms;
+            existing.tempo = payload.tempo;
 
             if (payload.is_paused) {
                 existing.position_ms = payload.position_ms;
                 if (Array.from(this.activeProgresses.values()).every(p => p.is_paused)) {
-                  this.masterLevels = { peak: 0, rms: 0 };
+                  this.masterLevels = { peak: 0, rms: 0, gain_reduction_db: 0 };
                 }
             }
         } else {
@@ -196,6 +347,31 @@ export const useAudioStore = defineStore("audio", {
         }
       });
 
+      // Changes made through the remote control API
+      listen<number>("master-volume-changed", (event) => {
+        this.masterVolume = event.payload;
+        this.saveSettings();
+      });
+
+      listen<string>("output-device-changed", (event) => {
+        this.currentDevice = event.payload;
+        this.saveSettings();
+      });
+
+      listen<{ device_id: string, device_name: string, role: "output" | "monitor" | "button" }>("device-lost", (event) => {
+        const { device_name, role } = event.payload;
+        const fallback = { output: "Default output", monitor: "no monitor", button: "the main output" }[role];
+        toast.warning(`${device_name} disconnected, using ${fallback}`);
+      });
+
+      listen<{ device_id: string, device_name: string, role: "output" | "monitor" | "button" }>("device-restored", (event) => {
+        toast.success(`${event.payload.device_name} reconnected`);
+      });
+
+      listen<string>("midi-feedback-error", (event) => {
+        toast.warning(`MIDI feedback unavailable: ${event.payload}`);
+      });
+
       listen<number>("global-shortcut-triggered", (event) => {
         const buttonId = event.payload;
         const button = this.buttons.find(b => b.id === buttonId);
@@ -203,6 +379,31 @@ export const useAudioStore = defineStore("audio", {
           this.playSound(buttonId);
         }
       });
+
+      listen<{ button_id: number, velocity: number | null }>("midi-triggered", (event) => {
+        const { button_id, velocity } = event.payload;
+        const button = this.buttons.find(b => b.id === button_id);
+        if (button && button.path) {
+          this.playSound(button_id, velocity ?? 1);
+        }
+      });
+
+      listen<"next" | "prev" | number>("page-change-requested", (event) => {
+        const page = event.payload;
+        if (page === "next") this.nextPage();
+        else if (page === "prev") this.prevPage();
+        else this.setPage(page);
+      });
+
+      listen<MidiBinding>("midi-learned", (event) => {
+        const binding = event.payload;
+        const { kind, channel, number } = binding.trigger;
+        this.midi.bindings = this.midi.bindings.filter(b =>
+          b.trigger.kind !== kind || b.trigger.channel !== channel || b.trigger.number !== number);
+        this.midi.bindings.push(binding);
+        this.saveSettings();
+        toast.success(`MIDI ${kind === "note" ? "note" : "CC"} ${number} (channel ${channel + 1}) assigned`);
+      });
     },
 
     async loadDevices() {
@@ -213,17 +414,56 @@ export const useAudioStore = defineStore("audio", {
       }
     },
 
-    async setOutputDevice(deviceName: string) {
+    async loadInputDevices() {
       try {
-        await invoke("set_audio_device", { deviceName });
-        this.currentDevice = deviceName;
+        this.inputDevices = await invoke("list_input_devices");
+      } catch (e) {
+        console.error("Failed to list input devices", e);
+      }
+    },
+
+    async setOutputDevice(deviceId: string) {
+      try {
+        // A renamed device comes back under a new id, which is saved in place of the old one
+        const selection = await invoke<DeviceSelection>("set_audio_device", { deviceId });
+        const device = this.devices.find(d => d.id === selection.id);
+        if (selection.suggested) {
+          // Keep the saved choice until the user picks the stand-in themselves
+          toast.warning(`Saved output not found, playing on ${device?.name ?? selection.id}`);
+          return;
+        }
+        this.currentDevice = selection.id;
         await this.saveSettings();
-        toast.success(`Output set to ${deviceName}`);
+        toast.success(`Output set to ${device?.name ?? selection.id}`);
       } catch (e) {
         console.error("Failed to set audio device", e);
       }
     },
 
+    async setAudioHost(host: string) {
+      try {
+        await invoke("set_audio_host", { host });
+        this.audioHost = host;
+        await this.saveSettings();
+        await this.loadDevices();
+        await this.loadInputDevices();
+      } catch (e) {
+        console.error("Failed to set audio host", e);
+        toast.error(`Audio host: ${e}`);
+      }
+    },
+
+    async setOutputConfig(deviceId: string, config: OutputConfig) {
+      try {
+        await invoke("set_output_config", { deviceId, config });
+        this.outputConfigs[deviceId] = config;
+        await this.saveSettings();
+      } catch (e) {
+        console.error("Failed to set output config", e);
+        toast.error(`Output format: ${e}`);
+      }
+    },
+
     async setMasterVolume(volume: number) {
       this.masterVolume = volume;
       try {
@@ -234,7 +474,7 @@ export const useAudioStore = defineStore("audio", {
       }
     },
 
-    async playSound(buttonId: number) {
+    async playSound(buttonId: number, volumeScale = 1) {
       const button = this.buttons.find(b => b.id === buttonId);
       if (!button || !button.path) return;
 
@@ -242,12 +482,17 @@ export const useAudioStore = defineStore("audio", {
         button.activeInstances++;
         button.isPaused = false;
         
-        await invoke("play_sound", { 
+        const instanceId = await invoke<number | null>("play_sound", { 
           id: buttonId.toString(), 
           path: button.path, 
           name: button.name,
-          volume: button.volume 
+          volume: button.volume * volumeScale,
+          options: { normalize: this.normalization.enabled },
         });
+        // The trigger policy consumed the press without starting a new instance
+        if (instanceId === null && button.activeInstances > 0) {
+          button.activeInstances--;
+        }
       } catch (e) {
         console.error("Failed to play sound", e);
         button.activeInstances--;
@@ -375,6 +620,11 @@ export const useAudioStore = defineStore("audio", {
           isPaused: false,
           activeInstances: 0,
           shortcut: null,
+          shortcutMode: "press",
+          shortcutFadeMs: 0,
+          trigger: "polyphonic",
+          chokeGroup: null,
+          device: null,
         });
       }
       
@@ -474,15 +724,54 @@ export const useAudioStore = defineStore("audio", {
               console.error("Failed to update button volume", e);
             });
           }
-          this.buttons[index] = {
+          const triggerChanged = (updates.trigger !== undefined && updates.trigger !== current.trigger)
+            || (updates.chokeGroup !== undefined && updates.chokeGroup !== current.chokeGroup);
+          const updated = {
             ...current,
             ...updates
           };
+          this.buttons[index] = updated;
+          if (triggerChanged) {
+            this.syncButtonTrigger(updated);
+          }
+          if (updates.device !== undefined && updates.device !== current.device) {
+            this.syncButtonDevice(updated);
+          }
           this.saveSettings();
         }
       }
     },
 
+    // The backend applies these to every trigger path, not just board clicks
+    async syncButtonTrigger(button: SoundButton) {
+      try {
+        await invoke("set_button_trigger", {
+          buttonId: button.id.toString(),
+          trigger: button.trigger,
+          chokeGroup: button.chokeGroup,
+        });
+      } catch (e) {
+        console.error("Failed to set button trigger", e);
+      }
+    },
+
+    async syncButtonDevice(button: SoundButton) {
+      try {
+        const selection = await invoke<DeviceSelection | null>("set_button_device", {
+          buttonId: button.id.toString(),
+          deviceId: button.device,
+        });
+        // A renamed device is saved under its new id; a suggested stand-in is not
+        if (selection && !selection.suggested && selection.id !== button.device) {
+          button.device = selection.id;
+          await this.saveSettings();
+        }
+      } catch (e) {
+        console.error("Failed to set button device", e);
+        toast.error(`Output for ${button.name} unavailable: ${e}`);
+      }
+    },
+
     async updateButtonWithCopy(id: number, path: string, originalName?: string) {
       try {
         const localPath = await invoke<string>("save_sound_file", { path });
@@ -519,10 +808,17 @@ export const useAudioStore = defineStore("audio", {
             volume: b.volume,
             color: b.color,
             shortcut: b.shortcut,
+            shortcutMode: b.shortcutMode,
+            shortcutFadeMs: b.shortcutFadeMs,
+            trigger: b.trigger,
+            chokeGroup: b.chokeGroup,
+            device: b.device,
         }));
         await store.set("buttons", buttonsToSave);
         await store.set("masterVolume", this.masterVolume);
         await store.set("currentDevice", this.currentDevice);
+        await store.set("audioHost", this.audioHost);
+        await store.set("outputConfigs", this.outputConfigs);
         await store.set("totalPages", this.totalPages);
         await store.set("pageNames", this.pageNames);
         await store.set("reducedMotion", this.reducedMotion);
@@ -530,6 +826,16 @@ export const useAudioStore = defineStore("audio", {
         await store.set("titlebarStyle", this.titlebarStyle);
         await store.set("controlsSide", this.controlsSide);
         await store.set("isDarkMode", this.isDarkMode);
+        await store.set("midi", this.midi);
+        await store.set("mic", this.mic);
+        await store.set("monitor", this.monitor);
+        await store.set("sampleCache", this.sampleCache);
+        await store.set("normalization", this.normalization);
+        await store.set("ducking", this.ducking);
+        await store.set("limiter", this.limiter);
+        await store.set("controlServer", this.controlServer);
+        await store.set("osc", this.osc);
+        await store.set("actionShortcuts", this.actionShortcuts);
         await store.save();
       } catch (e) {
         console.error("Failed to save settings", e);
@@ -547,6 +853,20 @@ export const useAudioStore = defineStore("audio", {
         const savedTitlebarStyle = await store.get<string>("titlebarStyle");
         const savedControlsSide = await store.get<string>("controlsSide");
         const savedIsDarkMode = await store.get<boolean>("isDarkMode");
+        const savedAudioHost = await store.get<string>("audioHost");
+        const savedOutputConfigs = await store.get<Record<string, OutputConfig>>("outputConfigs");
+        const savedVolume = await store.get<number>("masterVolume");
+        const savedDevice = await store.get<string>("currentDevice");
+        const savedActionShortcuts = await store.get<ActionShortcut[]>("actionShortcuts");
+        const savedMic = await store.get<MicSettings>("mic");
+        const savedMonitor = await store.get<MonitorSettings>("monitor");
+        const savedSampleCache = await store.get<SampleCacheSettings>("sampleCache");
+        const savedNormalization = await store.get<NormalizationSettings>("normalization");
+        const savedDucking = await store.get<DuckingSettings>("ducking");
+        const savedLimiter = await store.get<LimiterSettings>("limiter");
+        const savedMidi = await store.get<MidiSettings>("midi");
+        const savedControlServer = await store.get<ControlServerSettings>("controlServer");
+        const savedOsc = await store.get<OscSettings>("osc");
         
         if (savedTotalPages) {
           this.totalPages = savedTotalPages;
@@ -592,67 +912,277 @@ export const useAudioStore = defineStore("audio", {
             isPaused: false,
             activeInstances: 0,
             shortcut: saved?.shortcut || null,
+            shortcutMode: saved?.shortcutMode || "press",
+            shortcutFadeMs: saved?.shortcutFadeMs ?? 0,
+            trigger: saved?.trigger || "polyphonic",
+            chokeGroup: saved?.chokeGroup || null,
+            device: saved?.device || null,
           });
         }
         this.buttons = buttons;
+
+        // Device ids belong to a host, so it is restored before any device is opened
+        if (savedAudioHost) {
+          await this.setAudioHost(savedAudioHost);
+        }
+        if (savedOutputConfigs) {
+          for (const [deviceId, config] of Object.entries(savedOutputConfigs)) {
+            await this.setOutputConfig(deviceId, config);
+          }
+        }
+        await Promise.all(buttons
+          .filter(b => b.trigger !== "polyphonic" || b.chokeGroup)
+          .map(b => this.syncButtonTrigger(b)));
+        await Promise.all(buttons
+          .filter(b => b.device)
+          .map(b => this.syncButtonDevice(b)));
         
-        const savedVolume = await store.get<number>("masterVolume");
         if (savedVolume !== null && savedVolume !== undefined) {
           this.masterVolume = savedVolume;
           await invoke("update_master_volume", { volume: savedVolume });
         }
         
-        const savedDevice = await store.get<string>("currentDevice");
         if (savedDevice) {
+          // Kept as the choice if only a stand-in is found
+          this.currentDevice = savedDevice;
           await this.setOutputDevice(savedDevice);
         }
+
+        if (savedActionShortcuts) {
+          this.actionShortcuts = savedActionShortcuts;
+        }
+
+        if (savedMic) {
+          await this.updateMic(savedMic);
+        }
+
+        if (savedMonitor) {
+          await this.updateMonitor(savedMonitor);
+        }
+
+        if (savedSampleCache) {
+          this.sampleCache = { ...this.sampleCache, ...savedSampleCache };
+          await this.updateSampleCache();
+        }
+
+        if (savedNormalization) {
+          this.normalization = { ...this.normalization, ...savedNormalization };
+          await this.updateNormalization();
+        }
+
+        if (savedDucking) {
+          this.ducking = { ...this.ducking, ...savedDucking };
+          await this.updateDucking();
+        }
+
+        if (savedLimiter) {
+          this.limiter = { ...this.limiter, ...savedLimiter };
+          await this.updateLimiter();
+        }
+
+        if (savedMidi) {
+          this.midi = { ...this.midi, ...savedMidi };
+          await this.updateMidi();
+        }
+
+        if (savedControlServer) {
+          this.controlServer = { ...this.controlServer, ...savedControlServer };
+          await this.updateControlServer();
+        }
+
+        if (savedOsc) {
+          this.osc = { ...this.osc, ...savedOsc };
+          await this.updateOsc();
+        }
       } catch (e) {
         console.error("Failed to load settings", e);
       }
     },
 
-    async registerAllShortcuts() {
-      for (const button of this.buttons) {
-        if (button.shortcut) {
-          try {
-            await invoke("register_global_shortcut", { 
-              shortcut: button.shortcut, 
-              buttonId: button.id 
-            });
-          } catch (e) {
-            console.warn(`Failed to register shortcut ${button.shortcut} for button ${button.id}:`, e);
+    async updateMic(updates: Partial<MicSettings>) {
+      this.mic = { ...this.mic, ...updates };
+      try {
+        await invoke("set_mic_input", { deviceName: this.mic.device });
+        await invoke("update_mic_gain", { gain: this.mic.gain });
+        await invoke("set_mic_muted", { muted: this.mic.muted });
+        await this.saveSettings();
+      } catch (e) {
+        console.error("Failed to update microphone", e);
+        toast.error(`Microphone: ${e}`);
+      }
+    },
+
+    async updateMonitor(updates: Partial<MonitorSettings>) {
+      this.monitor = { ...this.monitor, ...updates };
+      try {
+        // Like the main output, a stand-in for a missing device is not saved over the choice
+        const selection = await invoke<DeviceSelection | null>("set_monitor_device", { deviceId: this.monitor.device });
+        if (selection && selection.id !== this.monitor.device) {
+          const device = this.devices.find(d => d.id === selection.id);
+          if (selection.suggested) {
+            toast.warning(`Saved monitor not found, monitoring on ${device?.name ?? selection.id}`);
+          } else {
+            this.monitor.device = selection.id;
           }
         }
+        await invoke("update_monitor_volume", { volume: this.monitor.volume });
+        await this.saveSettings();
+      } catch (e) {
+        console.error("Failed to update monitor", e);
+        toast.error(`Monitor: ${e}`);
+      }
+    },
+
+    async updateSampleCache() {
+      try {
+        await invoke("update_sample_cache", { settings: this.sampleCache });
+        await this.saveSettings();
+        // On load, init preloads once everything is restored
+        if (this.isLoaded) this.preloadCurrentPage();
+      } catch (e) {
+        console.error("Failed to update sample cache", e);
       }
     },
 
-    async setButtonShortcut(buttonId: number, shortcut: string | null) {
+    async updateNormalization() {
+      try {
+        const { target_lufs, ceiling_dbtp } = this.normalization;
+        await invoke("update_normalization", { settings: { target_lufs, ceiling_dbtp } });
+        await this.saveSettings();
+      } catch (e) {
+        console.error("Failed to update normalization", e);
+      }
+    },
+
+    async updateDucking() {
+      try {
+        await invoke("update_ducking", { settings: this.ducking });
+        await this.saveSettings();
+      } catch (e) {
+        console.error("Failed to update ducking", e);
+      }
+    },
+
+    async updateLimiter() {
+      try {
+        await invoke("update_limiter", { settings: this.limiter });
+        await this.saveSettings();
+      } catch (e) {
+        console.error("Failed to update limiter", e);
+      }
+    },
+
+    async updateMidi() {
+      try {
+        await invoke("update_midi_input", { settings: this.midi });
+        await this.saveSettings();
+      } catch (e) {
+        console.error("Failed to update MIDI input", e);
+        toast.error(`MIDI: ${e}`);
+      }
+    },
+
+    async updateControlServer() {
+      try {
+        // The token is generated on first start and must be kept for clients
+        this.controlServer = await invoke<ControlServerSettings>("update_control_server", { settings: this.controlServer });
+        await this.saveSettings();
+      } catch (e) {
+        console.error("Failed to update control server", e);
+        toast.error(`Control server: ${e}`);
+      }
+    },
+
+    async updateOsc() {
+      try {
+        await invoke("update_osc_server", { settings: this.osc });
+        await this.saveSettings();
+      } catch (e) {
+        console.error("Failed to update OSC server", e);
+        toast.error(`OSC: ${e}`);
+      }
+    },
+
+    async learnMidi(action: MidiAction) {
+      try {
+        await invoke("start_midi_learn", { action });
+        toast.info("Press a pad or move a control to assign it");
+      } catch (e) {
+        console.error("Failed to start MIDI learn", e);
+      }
+    },
+
+    async registerAllShortcuts() {
+      const bindings = [
+        ...this.buttons
+          .filter(b => b.shortcut)
+          .map(b => ({
+            shortcut: b.shortcut,
+            action: "play_button",
+            button_id: b.id,
+            mode: b.shortcutMode,
+            fade_ms: b.shortcutFadeMs,
+          })),
+        ...this.actionShortcuts,
+      ];
+      try {
+        const failures = await invoke<{ shortcut: string, error: string }[]>("replace_global_shortcuts", { bindings });
+        for (const failure of failures) {
+          console.warn(failure.error);
+        }
+        if (failures.length > 0) {
+          // The backend kept the previous set; retry with what the OS accepts
+          const refused = new Set(failures.map(f => f.shortcut));
+          await invoke("replace_global_shortcuts", { bindings: bindings.filter(b => !refused.has(b.shortcut)) });
+          toast.warning(`${failures.length} shortcut(s) could not be registered`);
+        }
+      } catch (e) {
+        console.error("Failed to register shortcuts", e);
+        toast.error(`${e}`);
+      }
+    },
+
+    async setButtonShortcut(buttonId: number, shortcut: string | null, mode: ShortcutMode = "press", fadeMs = 0) {
       const button = this.buttons.find(b => b.id === buttonId);
       if (!button) return;
 
-      if (button.shortcut) {
-        try {
-          await invoke("unregister_global_shortcut", { shortcut: button.shortcut });
-        } catch (e) {
-          console.warn("Failed to unregister old shortcut:", e);
-        }
+      const owner = shortcut ? this.buttons.find(b => b.id !== buttonId && b.shortcut === shortcut) : undefined;
+      if (owner) {
+        toast.error(`"${shortcut}" is already assigned to ${owner.name}`);
+        return;
+      }
+      if (shortcut && this.actionShortcuts.some(s => s.shortcut === shortcut)) {
+        toast.error(`"${shortcut}" is already assigned to an action`);
+        return;
       }
 
+      // The new shortcut is registered first so a conflict keeps the old one
       if (shortcut) {
         try {
           await invoke("register_global_shortcut", { 
             shortcut, 
-            buttonId 
+            action: { action: "play_button", button_id: buttonId, mode, fade_ms: fadeMs }
           });
-          button.shortcut = shortcut;
-          toast.success(`Shortcut "${shortcut}" assigned`);
         } catch (e) {
           console.error("Failed to register shortcut:", e);
-          toast.error(`Failed to register shortcut "${shortcut}"`);
-          button.shortcut = null;
+          toast.error(`${e}`);
+          return;
+        }
+      }
+
+      if (button.shortcut && button.shortcut !== shortcut) {
+        try {
+          await invoke("unregister_global_shortcut", { shortcut: button.shortcut });
+        } catch (e) {
+          console.warn("Failed to unregister old shortcut:", e);
         }
-      } else {
-        button.shortcut = null;
+      }
+
+      button.shortcut = shortcut;
+      if (shortcut) {
+        button.shortcutMode = mode;
+        button.shortcutFadeMs = fadeMs;
+        toast.success(`Shortcut "${shortcut}" assigned`);
       }
 
       this.saveSettings();
@@ -660,6 +1190,38 @@ export const useAudioStore = defineStore("audio", {
 
     async clearButtonShortcut(buttonId: number) {
       await this.setButtonShortcut(buttonId, null);
+    },
+
+    async setActionShortcut(binding: ActionShortcut) {
+      const owner = this.buttons.find(b => b.shortcut === binding.shortcut);
+      if (owner) {
+        toast.error(`"${binding.shortcut}" is already assigned to ${owner.name}`);
+        return;
+      }
+      try {
+        // Rebinding an accelerator to another action needs it released first
+        if (this.actionShortcuts.some(s => s.shortcut === binding.shortcut)) {
+          await invoke("unregister_global_shortcut", { shortcut: binding.shortcut });
+          this.actionShortcuts = this.actionShortcuts.filter(s => s.shortcut !== binding.shortcut);
+        }
+        const { shortcut, ...action } = binding;
+        await invoke("register_global_shortcut", { shortcut, action });
+        this.actionShortcuts.push(binding);
+      } catch (e) {
+        console.error("Failed to register shortcut:", e);
+        toast.error(`${e}`);
+      }
+      this.saveSettings();
+    },
+
+    async removeActionShortcut(shortcut: string) {
+      try {
+        await invoke("unregister_global_shortcut", { shortcut });
+      } catch (e) {
+        console.warn("Failed to unregister shortcut:", e);
+      }
+      this.actionShortcuts = this.actionShortcuts.filter(s => s.shortcut !== shortcut);
+      this.saveSettings();
     }
   },
 });